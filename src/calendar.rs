@@ -0,0 +1,202 @@
+use crate::Logbook;
+use std::collections::{BTreeMap, BTreeSet};
+use time::{Date, Duration, Month};
+
+/// Cells for days with entries, from the fewest words to the most
+const LEVELS: [char; 4] = ['░', '▒', '▓', '█'];
+
+/// ANSI styles for each level of days with entries
+const LEVEL_STYLES: [&str; 4] = ["38;5;22", "38;5;28", "38;5;34", "38;5;40"];
+
+/// Cell for days missing an entry
+const MISSING: char = '×';
+
+/// ANSI style for days missing an entry
+const MISSING_STYLE: &str = "1;31";
+
+/// Cell for days before or after the logbooks
+const OUTSIDE: char = ' ';
+
+/// ANSI style for the month and weekday labels
+const LABEL_STYLE: &str = "1";
+
+/// Weekday labels for each row, starting on Sunday. Every other row is left
+/// unlabelled to keep the calendar compact
+const WEEKDAYS: [&str; 7] = ["", "Mon", "", "Wed", "", "Fri", ""];
+
+/// Width of the weekday labels before the first column
+const LABEL_WIDTH: usize = 4;
+
+/// The writing activity of each effective date covered by some logbooks
+#[derive(Debug, Default, Clone)]
+pub struct Calendar {
+    /// The number of words written for each effective date with entries
+    words: BTreeMap<Date, usize>,
+    /// Effective dates within a logbook's entry range that have no entries
+    missing: BTreeSet<Date>,
+}
+
+impl Calendar {
+    /// Collect the writing activity of several logbooks. A logbook's entry
+    /// range runs from its start to its end, or to its last entry if it is
+    /// open-ended
+    pub fn new(logbooks: &[Logbook]) -> Self {
+        let mut calendar = Self::default();
+        let mut covered = BTreeSet::new();
+        for logbook in logbooks {
+            for entry in logbook {
+                *calendar
+                    .words
+                    .entry(entry.position().effective_date())
+                    .or_default() += entry.word_count();
+            }
+            let first = logbook.start().effective_date();
+            let last = logbook
+                .end()
+                .or_else(|| logbook.entries().last().map(|entry| entry.position()))
+                .map_or(first, |end| end.effective_date());
+            let mut date = first;
+            while date <= last {
+                covered.insert(date);
+                date += Duration::DAY;
+            }
+        }
+        calendar.missing = covered
+            .into_iter()
+            .filter(|date| !calendar.words.contains_key(date))
+            .collect();
+        calendar
+    }
+    /// Get the number of words written for an effective date, if it has
+    /// entries
+    pub fn words_on(&self, date: Date) -> Option<usize> {
+        self.words.get(&date).copied()
+    }
+    /// Check if an effective date within a logbook's entry range has no
+    /// entries
+    pub fn is_missing(&self, date: Date) -> bool {
+        self.missing.contains(&date)
+    }
+    /// Get the effective dates within a logbook's entry range that have no
+    /// entries
+    pub fn missing(&self) -> &BTreeSet<Date> {
+        &self.missing
+    }
+    /// Get every year with effective dates with entries or missing entries
+    pub fn years(&self) -> BTreeSet<i32> {
+        self.words
+            .keys()
+            .chain(&self.missing)
+            .map(|date| date.year())
+            .collect()
+    }
+
+    /// Render a calendar of a year in the style of a contribution graph, with
+    /// a column for each week and a row for each day of the week. Days are
+    /// shaded by the number of words written for them compared to the other
+    /// days of the year, and days missing an entry are marked
+    pub fn render(&self, year: i32, color: bool) -> String {
+        let paint = |text: &str, style: &str| {
+            if color {
+                format!("\x1b[{style}m{text}\x1b[0m")
+            } else {
+                text.to_owned()
+            }
+        };
+        let Ok(first) = Date::from_calendar_date(year, Month::January, 1) else {
+            return String::new();
+        };
+        let dates = (0..)
+            .map(|day| first + Duration::days(day))
+            .take_while(|date| date.year() == year)
+            .collect::<Vec<_>>();
+        let thresholds = quartiles(dates.iter().filter_map(|date| self.words_on(*date)));
+        // Place each date in its week's column, with weeks starting on Sunday
+        let offset = usize::from(first.weekday().number_days_from_sunday());
+        let columns = (dates.len() + offset).div_ceil(7);
+        let mut grid = vec![vec![None; columns]; 7];
+        for (index, date) in dates.iter().enumerate() {
+            let cell = index + offset;
+            grid[cell % 7][cell / 7] = Some(*date);
+        }
+        let mut rendered = String::new();
+        // Year and month labels above the first column each month starts in,
+        // where there is room
+        let mut months = " ".repeat(LABEL_WIDTH + columns);
+        let mut free = 0;
+        for date in dates.iter().filter(|date| date.day() == 1) {
+            let column = LABEL_WIDTH + (date.ordinal() as usize - 1 + offset) / 7;
+            let name = &date.month().to_string()[..3];
+            if column >= free && column + name.len() <= months.len() {
+                months.replace_range(column..column + name.len(), name);
+                free = column + name.len() + 1;
+            }
+        }
+        rendered.push_str(&paint(&year.to_string(), LABEL_STYLE));
+        rendered.push('\n');
+        rendered.push_str(&paint(months.trim_end(), LABEL_STYLE));
+        rendered.push('\n');
+        // A row for each day of the week
+        for (row, weekday) in grid.iter().zip(WEEKDAYS) {
+            rendered.push_str(&paint(
+                &format!("{weekday:<width$}", width = LABEL_WIDTH),
+                LABEL_STYLE,
+            ));
+            let cells = row
+                .iter()
+                .map(|date| match date {
+                    Some(date) => match self.words_on(*date) {
+                        Some(words) => {
+                            let level = thresholds
+                                .iter()
+                                .filter(|threshold| words > **threshold)
+                                .count();
+                            paint(&LEVELS[level].to_string(), LEVEL_STYLES[level])
+                        }
+                        None if self.is_missing(*date) => {
+                            paint(&MISSING.to_string(), MISSING_STYLE)
+                        }
+                        None => OUTSIDE.to_string(),
+                    },
+                    None => OUTSIDE.to_string(),
+                })
+                .collect::<String>();
+            rendered.push_str(cells.trim_end());
+            rendered.push('\n');
+        }
+        // Legend and totals
+        let written = dates
+            .iter()
+            .filter(|date| self.words_on(**date).is_some())
+            .count();
+        let missing = dates.iter().filter(|date| self.is_missing(**date)).count();
+        let legend = LEVELS
+            .iter()
+            .zip(LEVEL_STYLES)
+            .map(|(level, style)| paint(&level.to_string(), style))
+            .collect::<String>();
+        rendered.push_str(&format!(
+            "{:width$}Less {legend} More   {} missing\n",
+            "",
+            paint(&MISSING.to_string(), MISSING_STYLE),
+            width = LABEL_WIDTH,
+        ));
+        rendered.push_str(&format!(
+            "{:width$}{written} day(s) written, {missing} day(s) missing\n",
+            "",
+            width = LABEL_WIDTH,
+        ));
+        rendered
+    }
+}
+
+/// Get the word counts dividing days with entries into four levels, so that
+/// a day is at the level of the number of thresholds it is above
+fn quartiles(words: impl Iterator<Item = usize>) -> [usize; 3] {
+    let mut words = words.collect::<Vec<_>>();
+    if words.is_empty() {
+        return [0; 3];
+    }
+    words.sort_unstable();
+    [1, 2, 3].map(|quarter| words[(words.len() * quarter).div_ceil(4) - 1])
+}
@@ -0,0 +1,434 @@
+use crate::Mark;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// A problem found while reading a logbook. Its details are boxed so that
+/// results carrying one as their error stay small
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    details: Box<Details>,
+}
+
+/// The details of a diagnostic
+#[derive(Debug, Clone)]
+struct Details {
+    /// How serious this problem is
+    severity: Severity,
+    /// The stable identifier for this kind of problem
+    code: Code,
+    /// A description of this problem
+    message: String,
+    /// The file containing the problem, if known
+    file: Option<PathBuf>,
+    /// The byte range of the source text the problem was found at
+    span: Option<Span>,
+    /// The line and column the span starts at, once resolved against the
+    /// source text
+    location: Option<Location>,
+    /// The position of the entry the problem relates to, if any
+    mark: Option<Mark>,
+    /// Other places in the source text related to the problem
+    labels: Vec<Label>,
+    /// Additional information about the problem
+    notes: Vec<String>,
+}
+
+/// A secondary span of source text related to a diagnostic
+#[derive(Debug, Clone)]
+pub struct Label {
+    /// The span of source text being pointed at
+    span: Span,
+    /// A description of how this span relates to the diagnostic
+    message: String,
+}
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Something looks off, but the logbook was read as intended
+    Warning,
+    /// The logbook is wrong or could not be fully read
+    Error,
+}
+
+/// A byte range within a logbook's source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// The offset of the first byte of the range
+    start: usize,
+    /// The offset one past the last byte of the range
+    end: usize,
+}
+
+/// A human-readable position within a logbook's source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// The line number, starting from 1
+    line: usize,
+    /// The column (in characters), starting from 1
+    column: usize,
+}
+
+/// Stable identifiers for each kind of diagnostic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Code {
+    /// A file could not be read
+    UnreadableFile,
+    /// There is no page break after the preamble
+    MissingPageBreak,
+    /// The preamble has no entry range
+    MissingEntryRange,
+    /// An expected piece of text is missing
+    MissingToken,
+    /// A fixed piece of text does not have the expected value
+    UnexpectedToken,
+    /// There is extra text after an entry header
+    TrailingText,
+    /// A date could not be parsed
+    BadDate,
+    /// A time could not be parsed
+    BadTime,
+    /// A number could not be parsed
+    BadNumber,
+    /// A page header does not have the expected structure
+    MalformedPageHeader,
+    /// An entry header does not have the expected structure
+    MalformedEntryHeader,
+    /// A page does not start with a page header
+    MissingPageHeader,
+    /// A page break occurred where none was expected
+    UnexpectedPageBreak,
+    /// A page has a header but no entries
+    EmptyPage,
+    /// A page header does not match the entries on the page
+    PageHeaderMismatch,
+    /// There is no entry for an effective date
+    MissingEntry,
+    /// An entry has an unexpected effective date
+    DateMismatch,
+    /// An entry has an unexpected entry number
+    NumberMismatch,
+    /// A multi-page entry is not continued with a continuation marker
+    MissingContinuation,
+    /// Entry text appears before any subject line
+    MissingSubject,
+    /// The logbook does not end with the end mark given in the preamble
+    EndMismatch,
+    /// Entries are missing between one volume and the next
+    VolumeGap,
+    /// A volume starts before the previous volume ends
+    VolumeOverlap,
+    /// The same entry number appears in more than one volume
+    DuplicateEntry,
+    /// A volume with an open end is followed by another volume
+    OpenVolume,
+    /// A dialect configuration setting could not be read
+    BadConfig,
+    /// Dates or times are written in more than one style
+    MixedStyles,
+    /// A time zone annotation could not be read
+    BadZone,
+    /// An entry looks to have been finished before it was started
+    FinishedBeforeStarted,
+    /// An entry took longer than the maximum duration
+    LongEntry,
+    /// An entry was finished at the same time it was started
+    ZeroLength,
+    /// An entry was started before the previous entry was finished
+    OverlappingEntries,
+    /// An entry was recorded far from the day after the previous entry
+    DateDrift,
+    /// An entry was started in the future
+    FutureEntry,
+    /// Formatting a logbook would change what it says
+    FormatChanged,
+}
+
+impl Diagnostic {
+    /// Create a new error diagnostic
+    pub fn error(code: Code, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, code, message.into())
+    }
+
+    /// Create a new warning diagnostic
+    pub fn warning(code: Code, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, code, message.into())
+    }
+
+    fn new(severity: Severity, code: Code, message: String) -> Self {
+        Self {
+            details: Box::new(Details {
+                severity,
+                code,
+                message,
+                file: None,
+                span: None,
+                location: None,
+                mark: None,
+                labels: Vec::new(),
+                notes: Vec::new(),
+            }),
+        }
+    }
+
+    /// Attach the source span this diagnostic refers to
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.details.span = Some(span);
+        self.details.location = None;
+        self
+    }
+
+    /// Attach the file this diagnostic was found in
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.details.file = Some(file.into());
+        self
+    }
+
+    /// Attach the position of the entry this diagnostic relates to
+    pub fn with_mark(mut self, mark: Mark) -> Self {
+        self.details.mark = Some(mark);
+        self
+    }
+
+    /// Point at another span of source text related to this diagnostic
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.details.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Add a note to this diagnostic
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.details.notes.push(note.into());
+        self
+    }
+
+    /// Resolve the line and column of this diagnostic's span against the
+    /// source text it was found in
+    pub fn locate(mut self, source: &str) -> Self {
+        self.details.location = self
+            .details
+            .span
+            .map(|span| Location::of(source, span.start));
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.details.severity
+    }
+    pub fn code(&self) -> Code {
+        self.details.code
+    }
+    pub fn message(&self) -> &str {
+        &self.details.message
+    }
+    pub fn file(&self) -> Option<&Path> {
+        self.details.file.as_deref()
+    }
+    pub fn span(&self) -> Option<Span> {
+        self.details.span
+    }
+    pub fn location(&self) -> Option<Location> {
+        self.details.location
+    }
+    pub fn mark(&self) -> Option<&Mark> {
+        self.details.mark.as_ref()
+    }
+    pub fn labels(&self) -> &[Label] {
+        &self.details.labels
+    }
+    pub fn notes(&self) -> &[String] {
+        &self.details.notes
+    }
+
+    /// Encode this diagnostic as a single-line JSON object
+    pub fn to_json(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_else(|| "null".to_owned());
+        format!(
+            "{{\"code\":{},\"severity\":{},\"message\":{},\"file\":{},\"line\":{},\"column\":{},\"start\":{},\"end\":{},\"mark\":{},\"labels\":[{}],\"notes\":[{}]}}",
+            json_string(self.details.code.as_str()),
+            json_string(&self.details.severity.to_string()),
+            json_string(&self.details.message),
+            optional(self.details.file.as_ref().map(|file| json_string(&file.to_string_lossy()))),
+            optional(self.details.location.map(|location| location.line.to_string())),
+            optional(self.details.location.map(|location| location.column.to_string())),
+            optional(self.details.span.map(|span| span.start.to_string())),
+            optional(self.details.span.map(|span| span.end.to_string())),
+            optional(self.details.mark.as_ref().map(|mark| format!(
+                "{{\"effective_date\":{},\"entry_number\":{}}}",
+                json_string(&mark.effective_date().to_string()),
+                mark.entry_number(),
+            ))),
+            self.details.labels
+                .iter()
+                .map(|label| format!(
+                    "{{\"start\":{},\"end\":{},\"message\":{}}}",
+                    label.span.start,
+                    label.span.end,
+                    json_string(&label.message),
+                ))
+                .collect::<Vec<_>>()
+                .join(","),
+            self.details.notes
+                .iter()
+                .map(|note| json_string(note))
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+    }
+}
+
+impl Label {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Position prefix, as far as it is known
+        match (&self.details.file, self.details.location) {
+            (Some(file), Some(location)) => write!(f, "{}:{}: ", file.display(), location)?,
+            (Some(file), None) => write!(f, "{}: ", file.display())?,
+            (None, Some(location)) => write!(f, "{}: ", location)?,
+            (None, None) => (),
+        }
+        write!(
+            f,
+            "{}[{}]: {}",
+            self.details.severity, self.details.code, self.details.message
+        )?;
+        for note in &self.details.notes {
+            write!(f, "\n    note: {note}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+    pub fn start(&self) -> usize {
+        self.start
+    }
+    pub fn end(&self) -> usize {
+        self.end
+    }
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+    /// Get the smallest span covering both this span and another
+    pub fn to(&self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl Location {
+    /// Find the location of a byte offset within some source text
+    pub fn of(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+    pub fn line(&self) -> usize {
+        self.line
+    }
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Code {
+    /// Get the stable textual form of this code
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Code::UnreadableFile => "unreadable-file",
+            Code::MissingPageBreak => "missing-page-break",
+            Code::MissingEntryRange => "missing-entry-range",
+            Code::MissingToken => "missing-token",
+            Code::UnexpectedToken => "unexpected-token",
+            Code::TrailingText => "trailing-text",
+            Code::BadDate => "bad-date",
+            Code::BadTime => "bad-time",
+            Code::BadNumber => "bad-number",
+            Code::MalformedPageHeader => "malformed-page-header",
+            Code::MalformedEntryHeader => "malformed-entry-header",
+            Code::MissingPageHeader => "missing-page-header",
+            Code::UnexpectedPageBreak => "unexpected-page-break",
+            Code::EmptyPage => "empty-page",
+            Code::PageHeaderMismatch => "page-header-mismatch",
+            Code::MissingEntry => "missing-entry",
+            Code::DateMismatch => "date-mismatch",
+            Code::NumberMismatch => "number-mismatch",
+            Code::MissingContinuation => "missing-continuation",
+            Code::MissingSubject => "missing-subject",
+            Code::EndMismatch => "end-mismatch",
+            Code::VolumeGap => "volume-gap",
+            Code::VolumeOverlap => "volume-overlap",
+            Code::DuplicateEntry => "duplicate-entry",
+            Code::OpenVolume => "open-volume",
+            Code::BadConfig => "bad-config",
+            Code::MixedStyles => "mixed-styles",
+            Code::BadZone => "bad-zone",
+            Code::FinishedBeforeStarted => "finished-before-started",
+            Code::LongEntry => "long-entry",
+            Code::ZeroLength => "zero-length",
+            Code::OverlappingEntries => "overlapping-entries",
+            Code::DateDrift => "date-drift",
+            Code::FutureEntry => "future-entry",
+            Code::FormatChanged => "format-changed",
+        }
+    }
+}
+
+impl fmt::Display for Code {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Encode a string as a JSON string literal
+fn json_string(string: &str) -> String {
+    let mut encoded = String::with_capacity(string.len() + 2);
+    encoded.push('"');
+    for character in string.chars() {
+        match character {
+            '"' => encoded.push_str("\\\""),
+            '\\' => encoded.push_str("\\\\"),
+            '\n' => encoded.push_str("\\n"),
+            '\r' => encoded.push_str("\\r"),
+            '\t' => encoded.push_str("\\t"),
+            control if control.is_control() => {
+                encoded.push_str(&format!("\\u{:04x}", control as u32))
+            }
+            other => encoded.push(other),
+        }
+    }
+    encoded.push('"');
+    encoded
+}
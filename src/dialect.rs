@@ -0,0 +1,493 @@
+use crate::diagnostic::{Code, Diagnostic, Span};
+use crate::parse;
+use std::path::Path;
+use time::format_description::{self, BorrowedFormatItem, OwnedFormatItem};
+use time::macros::{date, format_description, time};
+use time::{Date, Time};
+
+/// Page boundary marker
+const PAGE_MARKER: &str = "-----";
+
+/// Page header date and number separator
+const PAGE_RANGE_SEPARATOR: &str = "-";
+
+/// Entry header start
+const HEADER_START: &str = "Entry";
+
+/// Separator between entry number and date in entry header
+const HEADER_POST_NUMBER: &str = ":";
+
+/// Separator between entry date and start time in entry header
+const HEADER_STARTED: &str = "started";
+
+/// Separator between entry start and finished time in entry header
+const HEADER_FINISHED: &str = "finished";
+
+/// Prefix for the subject lines of an entry
+const SUBJECT_PREFIX: &str = "    ";
+
+/// Marker for multi-page entries
+const MULTI_PAGE: &str = "(->)";
+
+/// Start of the preamble entry rage
+const ENTRY_RANGE_START: &str = "Entries from";
+
+/// Separator between the date and entry number of the preamble entry range
+const ENTRY_RANGE_MARK_SEPARATOR: &str = "-";
+
+/// Separator between the start and end marks of the preamble entry range
+const ENTRY_RANGE_SEPARATOR: &str = "to";
+
+/// Placeholder for the ending entry number of the preamble entry range
+const ENTRY_RANGE_PLACEHOLDER: &str = "_";
+
+/// Start of a preamble declaration of the hour before which entries count for
+/// the previous day
+const DAY_START: &str = "Days start at";
+
+/// Hour before which an entry will be considered as being written on the
+/// previous day
+pub(crate) const NEXT_DAY: u8 = 6;
+
+/// Format of dates
+const DATE_FORMAT: &str = "[month padding:none]/[day padding:none]/[year]";
+
+/// Format of times
+const TIME_FORMAT: &str = "[hour padding:none repr:12]:[minute] [period]";
+
+/// Format of dates written in the ISO 8601 style
+const ISO_DATE_FORMAT: &str = "[year]-[month]-[day]";
+
+/// Format of dates written in the ISO 8601 style, used when reading
+const ISO_DATE_INPUT_FORMAT: &[BorrowedFormatItem] =
+    format_description!("[year]-[month padding:none]-[day padding:none]");
+
+/// Format of times written in the 24-hour style
+const TWENTY_FOUR_HOUR_FORMAT: &str = "[hour]:[minute]";
+
+/// Format of times written in the 24-hour style, used when reading
+const TWENTY_FOUR_HOUR_INPUT_FORMAT: &[BorrowedFormatItem] =
+    format_description!("[hour padding:none]:[minute padding:none]");
+
+/// Date and time components whose padding is ignored when reading
+const PADDED_COMPONENTS: [&str; 5] = ["day", "month", "hour", "minute", "second"];
+
+/// Settings that are matched against single words of a line, and so must not
+/// contain whitespace
+const SINGLE_WORDS: [&str; 7] = [
+    "header_start",
+    "header_post_number",
+    "header_started",
+    "header_finished",
+    "entry_range_mark_separator",
+    "entry_range_separator",
+    "entry_range_placeholder",
+];
+
+/// The words, markers and formats a logbook is written with. The default is
+/// the format described in the README
+#[derive(Debug, Clone)]
+pub struct Dialect {
+    /// Page boundary marker
+    page_marker: String,
+    /// Separator between the start and end of page header ranges
+    page_range_separator: String,
+    /// Entry header start
+    header_start: String,
+    /// Separator between entry number and date in entry header
+    header_post_number: String,
+    /// Separator between entry date and start time in entry header
+    header_started: String,
+    /// Separator between entry start and finished time in entry header
+    header_finished: String,
+    /// Prefix for the subject lines of an entry
+    subject_prefix: String,
+    /// Marker for multi-page entries
+    multi_page: String,
+    /// Start of the preamble entry range
+    entry_range_start: String,
+    /// Separator between the date and entry number of the preamble entry range
+    entry_range_mark_separator: String,
+    /// Separator between the start and end marks of the preamble entry range
+    entry_range_separator: String,
+    /// Placeholder for the ending entry number of the preamble entry range
+    entry_range_placeholder: String,
+    /// Start of a preamble declaration of the hour before which entries count
+    /// for the previous day
+    day_start: String,
+    /// Hour before which an entry will be considered as being written on the
+    /// previous day
+    next_day: u8,
+    /// Format of dates, used when writing
+    date_format: OwnedFormatItem,
+    /// Format of dates, used when reading
+    date_input_format: OwnedFormatItem,
+    /// Format of times, used when writing
+    time_format: OwnedFormatItem,
+    /// Format of times, used when reading
+    time_input_format: OwnedFormatItem,
+    /// Number of words a time is written with
+    time_words: usize,
+}
+
+/// The styles the dates and times of a logbook are written in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Style {
+    /// The style of dates
+    date: DateStyle,
+    /// The style of times
+    time: TimeStyle,
+}
+
+/// A way of writing dates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DateStyle {
+    /// In the dialect's date format ("1/31/2000" by default)
+    #[default]
+    Dialect,
+    /// In the ISO 8601 format ("2000-01-31")
+    Iso,
+}
+
+/// A way of writing times
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimeStyle {
+    /// In the dialect's time format ("8:15 PM" by default)
+    #[default]
+    Dialect,
+    /// On a 24-hour clock ("20:15")
+    TwentyFourHour,
+}
+
+impl Style {
+    pub fn new(date: DateStyle, time: TimeStyle) -> Self {
+        Self { date, time }
+    }
+    pub fn date(&self) -> DateStyle {
+        self.date
+    }
+    pub fn time(&self) -> TimeStyle {
+        self.time
+    }
+}
+
+impl DateStyle {
+    /// Every date style, in the order they are tried when reading
+    pub const ALL: [DateStyle; 2] = [DateStyle::Dialect, DateStyle::Iso];
+}
+
+impl TimeStyle {
+    /// Every time style, in the order they are tried when reading
+    pub const ALL: [TimeStyle; 2] = [TimeStyle::Dialect, TimeStyle::TwentyFourHour];
+}
+
+impl Default for Dialect {
+    fn default() -> Self {
+        let mut dialect = Self {
+            page_marker: PAGE_MARKER.to_owned(),
+            page_range_separator: PAGE_RANGE_SEPARATOR.to_owned(),
+            header_start: HEADER_START.to_owned(),
+            header_post_number: HEADER_POST_NUMBER.to_owned(),
+            header_started: HEADER_STARTED.to_owned(),
+            header_finished: HEADER_FINISHED.to_owned(),
+            subject_prefix: SUBJECT_PREFIX.to_owned(),
+            multi_page: MULTI_PAGE.to_owned(),
+            entry_range_start: ENTRY_RANGE_START.to_owned(),
+            entry_range_mark_separator: ENTRY_RANGE_MARK_SEPARATOR.to_owned(),
+            entry_range_separator: ENTRY_RANGE_SEPARATOR.to_owned(),
+            entry_range_placeholder: ENTRY_RANGE_PLACEHOLDER.to_owned(),
+            day_start: DAY_START.to_owned(),
+            next_day: NEXT_DAY,
+            date_format: OwnedFormatItem::Compound(Box::new([])),
+            date_input_format: OwnedFormatItem::Compound(Box::new([])),
+            time_format: OwnedFormatItem::Compound(Box::new([])),
+            time_input_format: OwnedFormatItem::Compound(Box::new([])),
+            time_words: 0,
+        };
+        dialect
+            .set("date_format", DATE_FORMAT)
+            .expect("default date format is valid");
+        dialect
+            .set("time_format", TIME_FORMAT)
+            .expect("default time format is valid");
+        dialect
+    }
+}
+
+impl Dialect {
+    /// Read a dialect from a configuration file
+    pub fn load(file: &Path) -> Result<Self, Diagnostic> {
+        let text = parse::read_file(file)?;
+        Self::parse(&text).map_err(|error| error.with_file(file))
+    }
+
+    /// Read a dialect from configuration text. Each line sets one setting as
+    /// `name = value`, where the value may be quoted to keep surrounding
+    /// whitespace and use the escapes `\t`, `\n`, `\"` and `\\`. Blank lines
+    /// and lines starting with `#` are ignored, and settings that aren't given
+    /// keep their default values
+    pub fn parse(config: &str) -> Result<Self, Diagnostic> {
+        let mut dialect = Self::default();
+        let mut offset = 0;
+        for line in config.split_inclusive('\n') {
+            let span = Span::new(offset, offset + line.trim_end().len());
+            offset += line.len();
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = |message: String| {
+                Diagnostic::error(Code::BadConfig, message)
+                    .with_span(span)
+                    .locate(config)
+            };
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| bad("expected a setting as `name = value`".to_owned()))?;
+            let value = unquote(value.trim()).map_err(bad)?;
+            dialect.set(name.trim(), &value).map_err(bad)?;
+        }
+        Ok(dialect)
+    }
+
+    /// Change a setting, given by the name used in configuration files. Dates
+    /// and times are given as format descriptions for the `time` crate, and
+    /// dates must be written without spaces
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if value.is_empty() {
+            return Err(format!("{name} must not be empty"));
+        }
+        if SINGLE_WORDS.contains(&name) && value.split_whitespace().count() != 1 {
+            return Err(format!("{name} must be a single word"));
+        }
+        let text = match name {
+            "page_marker" => &mut self.page_marker,
+            "page_range_separator" => &mut self.page_range_separator,
+            "header_start" => &mut self.header_start,
+            "header_post_number" => &mut self.header_post_number,
+            "header_started" => &mut self.header_started,
+            "header_finished" => &mut self.header_finished,
+            "subject_prefix" => &mut self.subject_prefix,
+            "multi_page" => &mut self.multi_page,
+            "entry_range_start" => &mut self.entry_range_start,
+            "entry_range_mark_separator" => &mut self.entry_range_mark_separator,
+            "entry_range_separator" => &mut self.entry_range_separator,
+            "entry_range_placeholder" => &mut self.entry_range_placeholder,
+            "day_start" => &mut self.day_start,
+            "next_day" => {
+                self.next_day = value.parse().ok().filter(|hour| *hour < 24).ok_or(format!(
+                    "next_day must be an hour from 0 to 23, got {value}"
+                ))?;
+                return Ok(());
+            }
+            "date_format" => {
+                let (format, input_format) = compile(name, value)?;
+                let written = Date::MIN
+                    .format(&format)
+                    .map_err(|error| format!("bad {name} - {error}"))?;
+                if written.split_whitespace().count() != 1 {
+                    return Err(format!("{name} must write dates without spaces"));
+                }
+                self.date_format = format;
+                self.date_input_format = input_format;
+                return Ok(());
+            }
+            "time_format" => {
+                let (format, input_format) = compile(name, value)?;
+                let written = Time::MIDNIGHT
+                    .format(&format)
+                    .map_err(|error| format!("bad {name} - {error}"))?;
+                self.time_words = written.split_whitespace().count().max(1);
+                self.time_format = format;
+                self.time_input_format = input_format;
+                return Ok(());
+            }
+            _ => return Err(format!("unknown setting {name}")),
+        };
+        *text = value.to_owned();
+        Ok(())
+    }
+
+    pub fn page_marker(&self) -> &str {
+        &self.page_marker
+    }
+    pub fn page_range_separator(&self) -> &str {
+        &self.page_range_separator
+    }
+    pub fn header_start(&self) -> &str {
+        &self.header_start
+    }
+    pub fn header_post_number(&self) -> &str {
+        &self.header_post_number
+    }
+    pub fn header_started(&self) -> &str {
+        &self.header_started
+    }
+    pub fn header_finished(&self) -> &str {
+        &self.header_finished
+    }
+    pub fn subject_prefix(&self) -> &str {
+        &self.subject_prefix
+    }
+    pub fn multi_page(&self) -> &str {
+        &self.multi_page
+    }
+    pub fn entry_range_start(&self) -> &str {
+        &self.entry_range_start
+    }
+    pub fn entry_range_mark_separator(&self) -> &str {
+        &self.entry_range_mark_separator
+    }
+    pub fn entry_range_separator(&self) -> &str {
+        &self.entry_range_separator
+    }
+    pub fn entry_range_placeholder(&self) -> &str {
+        &self.entry_range_placeholder
+    }
+    pub fn day_start(&self) -> &str {
+        &self.day_start
+    }
+    pub fn next_day(&self) -> u8 {
+        self.next_day
+    }
+    pub fn date_format(&self) -> &OwnedFormatItem {
+        &self.date_format
+    }
+    pub fn date_input_format(&self) -> &OwnedFormatItem {
+        &self.date_input_format
+    }
+    pub fn time_format(&self) -> &OwnedFormatItem {
+        &self.time_format
+    }
+    pub fn time_input_format(&self) -> &OwnedFormatItem {
+        &self.time_input_format
+    }
+    /// Get the number of words a time is written with, such as 2 for
+    /// "8:00 PM"
+    pub fn time_words(&self) -> usize {
+        self.time_words
+    }
+
+    /// Get the number of words a time written in a style is written with
+    pub fn time_words_in(&self, style: TimeStyle) -> usize {
+        match style {
+            TimeStyle::Dialect => self.time_words,
+            TimeStyle::TwentyFourHour => 1,
+        }
+    }
+
+    /// Read a date written in a style
+    pub fn read_date(&self, text: &str, style: DateStyle) -> Result<Date, time::error::Parse> {
+        match style {
+            DateStyle::Dialect => Date::parse(text, &self.date_input_format),
+            DateStyle::Iso => Date::parse(text, ISO_DATE_INPUT_FORMAT),
+        }
+    }
+
+    /// Read a time written in a style
+    pub fn read_time(&self, text: &str, style: TimeStyle) -> Result<Time, time::error::Parse> {
+        match style {
+            TimeStyle::Dialect => Time::parse(text, &self.time_input_format),
+            TimeStyle::TwentyFourHour => Time::parse(text, TWENTY_FOUR_HOUR_INPUT_FORMAT),
+        }
+    }
+
+    /// Get a copy of this dialect that writes dates and times in a style
+    pub fn styled(&self, style: Style) -> Self {
+        let mut dialect = self.clone();
+        if let DateStyle::Iso = style.date {
+            dialect
+                .set("date_format", ISO_DATE_FORMAT)
+                .expect("ISO date format is valid");
+        }
+        if let TimeStyle::TwentyFourHour = style.time {
+            dialect
+                .set("time_format", TWENTY_FOUR_HOUR_FORMAT)
+                .expect("24-hour time format is valid");
+        }
+        dialect
+    }
+
+    /// Write an example date in a style, for use in messages
+    pub fn example_date(&self, style: DateStyle) -> String {
+        date!(2000 - 01 - 31)
+            .format(
+                self.styled(Style::new(style, TimeStyle::Dialect))
+                    .date_format(),
+            )
+            .expect("dialect date formats are checked when set")
+    }
+
+    /// Write an example time in a style, for use in messages
+    pub fn example_time(&self, style: TimeStyle) -> String {
+        time!(20:15)
+            .format(
+                self.styled(Style::new(DateStyle::Dialect, style))
+                    .time_format(),
+            )
+            .expect("dialect time formats are checked when set")
+    }
+}
+
+/// Compile a format description into the formats used for writing and
+/// reading. When reading, numbers may be written with or without padding
+fn compile(name: &str, description: &str) -> Result<(OwnedFormatItem, OwnedFormatItem), String> {
+    let compile = |description: &str| {
+        format_description::parse_owned::<2>(description)
+            .map_err(|error| format!("bad {name} - {error}"))
+    };
+    Ok((compile(description)?, compile(&unpadded(description))?))
+}
+
+/// Rewrite a format description so that numeric components without an
+/// explicit padding accept any padding
+fn unpadded(description: &str) -> String {
+    let mut rewritten = String::new();
+    let mut rest = description;
+    while let Some(start) = rest.find('[') {
+        let Some(end) = rest[start..].find(']').map(|end| start + end) else {
+            break;
+        };
+        let component = &rest[start + 1..end];
+        let name = component.split_whitespace().next().unwrap_or_default();
+        rewritten.push_str(&rest[..end]);
+        if PADDED_COMPONENTS.contains(&name) && !component.contains("padding:") {
+            rewritten.push_str(" padding:none");
+        }
+        rewritten.push(']');
+        rest = &rest[end + 1..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+/// Remove the quotes from a configuration value, if it has them, and resolve
+/// its escapes
+pub(crate) fn unquote(value: &str) -> Result<String, String> {
+    let Some(quoted) = value.strip_prefix('"') else {
+        return Ok(value.to_owned());
+    };
+    let quoted = quoted
+        .strip_suffix('"')
+        .ok_or("no closing quote".to_owned())?;
+    let mut unquoted = String::new();
+    let mut characters = quoted.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            unquoted.push(character);
+            continue;
+        }
+        unquoted.push(match characters.next() {
+            Some('t') => '\t',
+            Some('n') => '\n',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(other) => return Err(format!("unknown escape \\{other}")),
+            None => return Err("unfinished escape".to_owned()),
+        });
+    }
+    Ok(unquoted)
+}
@@ -0,0 +1,48 @@
+use crate::diagnostic::Diagnostic;
+use crate::dialect::Dialect;
+use crate::parse;
+use crate::syntax::{Edit, SyntaxTree};
+use crate::{write, Logbook};
+
+/// Recompute every page header of a logbook written in a dialect from the
+/// entries on its page, rewriting only the page headers that change. Logbooks
+/// that cannot be parsed are left alone and the parse error is returned
+pub fn fix_page_headers(logbook: &str, dialect: &Dialect) -> Result<String, Diagnostic> {
+    let tree = SyntaxTree::with_dialect(logbook, dialect);
+    let (parsed, _) = parse::parse_tree(&tree)?.into_parts();
+    Ok(tree.edit(&page_header_edits(&tree, &parsed)))
+}
+
+/// Find the edits that make each page header match the entry numbers and
+/// recorded dates of the first and last entries on its page, given the logbook
+/// parsed from the tree. An entry continued from the previous page counts as
+/// being on both pages, and dates are written in the logbook's own style. Pages
+/// with an entry that could not be parsed are left alone
+pub fn page_header_edits(tree: &SyntaxTree, logbook: &Logbook) -> Vec<Edit> {
+    let dialect = &tree.dialect().styled(logbook.style());
+    let mut edits = Vec::new();
+    for (node, page) in tree.pages().iter().zip(logbook.pages()) {
+        if page.entries().len() != node.entries().len() {
+            continue;
+        }
+        let (Some(header), Some(first), Some(last)) =
+            (node.header(), page.entries().first(), page.entries().last())
+        else {
+            continue;
+        };
+        // Rewrite the header's ranges if they don't already match, keeping
+        // any time zone line after them
+        let ranges = header.dates().map_or(header.span(), |dates| {
+            header.numbers().span().to(dates.span())
+        });
+        let fixed = write::write_page_range(
+            dialect,
+            (first.entry_number(), first.recorded_date()),
+            (last.entry_number(), last.recorded_date()),
+        );
+        if tree.text(ranges) != fixed {
+            edits.push(Edit::new(ranges, fixed));
+        }
+    }
+    edits
+}
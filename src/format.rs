@@ -0,0 +1,249 @@
+use crate::diagnostic::{Code, Diagnostic, Span};
+use crate::dialect::Dialect;
+use crate::parse;
+use crate::syntax::{Edit, LineNode, SyntaxTree, ENTRY_RANGE_WORDS};
+use crate::{fix, write, Entry, Logbook};
+use std::collections::HashMap;
+
+/// Rewrite a logbook written in a dialect into canonical form, keeping its
+/// line and page layout. Blank lines between components are normalized to a
+/// single one, trailing whitespace and lines of only whitespace are removed,
+/// continuation markers are spaced consistently, dates and times in the
+/// preamble and entry headers are written in the logbook's own style, and page
+/// headers are recomputed from the entries on each page. Logbooks that cannot
+/// be parsed are left alone and the parse error is returned, as are logbooks
+/// whose entries would read differently once formatted
+pub fn format(logbook: &str, dialect: &Dialect) -> Result<String, Diagnostic> {
+    let tree = SyntaxTree::with_dialect(logbook, dialect);
+    let (parsed, _) = parse::parse_tree(&tree)?.into_parts();
+    // Tidy the whitespace first, so that values are rewritten in tidy lines
+    let tidied = tree.edit(&whitespace_edits(&tree));
+    let tidied_tree = SyntaxTree::with_dialect(&tidied, dialect);
+    let (tidied_parsed, _) = parse::parse_tree(&tidied_tree)
+        .map_err(|_| changed(&tree, None))?
+        .into_parts();
+    let mut edits = value_edits(&tidied_tree, &tidied_parsed);
+    edits.extend(fix::page_header_edits(&tidied_tree, &tidied_parsed));
+    let formatted = tidied_tree.edit(&edits);
+    // Only hand back the formatted logbook if it says the same thing
+    let (reparsed, _) = parse::parse_with_dialect(&formatted, dialect)
+        .map_err(|_| changed(&tree, None))?
+        .into_parts();
+    check_unchanged(&tree, &parsed, &reparsed)?;
+    Ok(formatted)
+}
+
+/// Find the edits that tidy the whitespace of a tree without moving any of
+/// its components: trailing whitespace is removed from every line, lines of
+/// only whitespace are removed from components, and components are separated
+/// by a single blank line
+fn whitespace_edits(tree: &SyntaxTree) -> Vec<Edit> {
+    let source = tree.source();
+    let mut edits = Vec::new();
+    // The components after the preamble, with their tidied text. Components
+    // that were only whitespace are dropped along with the blank lines around
+    // them
+    let mut components = Vec::new();
+    for page in tree.pages() {
+        components.push(page.marker());
+        components.extend(page.header().map(|header| header.span()));
+        components.extend(page.entries().iter().map(|entry| entry.span()));
+    }
+    let components = components
+        .into_iter()
+        .map(|span| (span, tidy(tree.text(span))))
+        .filter(|(_, tidied)| !tidied.is_empty())
+        .collect::<Vec<_>>();
+    // The preamble keeps its blank lines, apart from those at its end
+    let preamble = tree.preamble().span();
+    let mut lines = tree
+        .text(preamble)
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>();
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    let separator = if lines.is_empty() {
+        ""
+    } else if components.is_empty() {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    edits.push(Edit::new(preamble, lines.join("\n") + separator));
+    for (index, (span, tidied)) in components.iter().enumerate() {
+        edits.push(Edit::new(*span, tidied.clone()));
+        let (gap, separator) = match components.get(index + 1) {
+            Some((next, _)) => (Span::new(span.end(), next.start()), "\n\n"),
+            None => (Span::new(span.end(), source.len()), "\n"),
+        };
+        edits.push(Edit::new(gap, separator.to_owned()));
+    }
+    edits
+        .into_iter()
+        .filter(|edit| tree.text(edit.span()) != edit.replacement())
+        .collect()
+}
+
+/// Remove trailing whitespace from each line of a component, and the lines
+/// that were only whitespace
+fn tidy(component: &str) -> String {
+    component
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Find the edits that rewrite the preamble entry range and entry headers in
+/// the logbook's own style, and space continuation markers consistently, given
+/// the logbook parsed from the tree
+fn value_edits(tree: &SyntaxTree, logbook: &Logbook) -> Vec<Edit> {
+    let source = tree.source();
+    let dialect = tree.dialect();
+    let written = &dialect.styled(logbook.style());
+    let mut edits = Vec::new();
+    // Rewrite the entry range if its line contains nothing else
+    if let Some(range) = tree
+        .preamble()
+        .entry_range()
+        .filter(|range| range.words().len() == ENTRY_RANGE_WORDS)
+    {
+        let span = range.span();
+        let line_start = source[..span.start()]
+            .rfind('\n')
+            .map_or(0, |index| index + 1);
+        let line_end = source[span.end()..]
+            .find('\n')
+            .map_or(source.len(), |index| span.end() + index);
+        if source[line_start..span.start()].trim().is_empty()
+            && source[span.end()..line_end].trim().is_empty()
+        {
+            edits.push(Edit::new(
+                span,
+                write::write_preamble(written, logbook.start(), logbook.end()),
+            ));
+        }
+    }
+    for entry in tree.pages().iter().flat_map(|page| page.entries()) {
+        // Entry headers
+        if let Some(header) = entry.header() {
+            if let Some(values) = parse::read_entry_header(tree.text(header.span()), dialect) {
+                edits.push(Edit::new(
+                    header.span(),
+                    write::write_header_line(written, &values),
+                ));
+            }
+        }
+        // Continuation markers, separated from the text by a single space
+        for line in entry.lines() {
+            let LineNode::Text {
+                span,
+                continued,
+                text,
+                continues,
+            } = *line
+            else {
+                continue;
+            };
+            if continued.is_none() && continues.is_none() {
+                continue;
+            }
+            let mut body = tree.text(text);
+            if continued.is_some() {
+                body = body.trim_start();
+            }
+            if continues.is_some() {
+                body = body.trim_end();
+            }
+            let spaced = [
+                continued.map(|marker| tree.text(marker)),
+                Some(body).filter(|body| !body.is_empty()),
+                continues.map(|marker| tree.text(marker)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+            edits.push(Edit::new(span, spaced));
+        }
+    }
+    edits
+        .into_iter()
+        .filter(|edit| tree.text(edit.span()) != edit.replacement())
+        .collect()
+}
+
+/// Check that a formatted logbook has the same entries as the logbook it was
+/// formatted from, allowing only for changes to the whitespace in their
+/// subjects and text
+fn check_unchanged(tree: &SyntaxTree, before: &Logbook, after: &Logbook) -> Result<(), Diagnostic> {
+    if before.start() != after.start() || before.end() != after.end() || before.len() != after.len()
+    {
+        return Err(changed(tree, None));
+    }
+    for (before_entry, after_entry) in before.iter().zip(after.iter()) {
+        if !same_entry(before_entry, after_entry) {
+            return Err(changed(tree, Some(before_entry)));
+        }
+    }
+    Ok(())
+}
+
+/// Check if two entries are the same, other than the whitespace in their
+/// subjects and text
+fn same_entry(before: &Entry, after: &Entry) -> bool {
+    let words = |text: &str| {
+        text.split_whitespace()
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+    };
+    before.position() == after.position()
+        && before.started() == after.started()
+        && before.finished() == after.finished()
+        && before.started_zone() == after.started_zone()
+        && before.finished_zone() == after.finished_zone()
+        && before.contents().len() == after.contents().len()
+        && before
+            .contents()
+            .iter()
+            .zip(after.contents())
+            .all(|(before, after)| {
+                before.subject().trim() == after.subject().trim()
+                    && words(before.text()) == words(after.text())
+            })
+}
+
+/// Report that formatting would change what a logbook says, pointing at the
+/// header of the first entry that would change where known
+fn changed(tree: &SyntaxTree, entry: Option<&Entry>) -> Diagnostic {
+    let Some(entry) = entry else {
+        return Diagnostic::error(
+            Code::FormatChanged,
+            "formatting would change the entries of the logbook",
+        );
+    };
+    let number = entry.position().entry_number();
+    let mut headers = HashMap::new();
+    for header in tree
+        .pages()
+        .iter()
+        .flat_map(|page| page.entries())
+        .filter_map(|entry| entry.header())
+    {
+        if let Some(values) = parse::read_entry_header(tree.text(header.span()), tree.dialect()) {
+            headers.entry(values.number).or_insert(header.span());
+        }
+    }
+    let diagnostic = Diagnostic::error(
+        Code::FormatChanged,
+        format!("formatting would change entry {number}"),
+    )
+    .with_mark(entry.position().clone());
+    match headers.get(&number) {
+        Some(span) => diagnostic.with_span(*span).locate(tree.source()),
+        None => diagnostic,
+    }
+}
@@ -1,10 +1,40 @@
-use time::{Date, PrimitiveDateTime};
+use dialect::Style;
+use subject::SubjectPath;
+use time::{Date, Duration, OffsetDateTime, PrimitiveDateTime};
+use zone::Zone;
 
+pub mod calendar;
+pub mod diagnostic;
+pub mod dialect;
+pub mod fix;
+pub mod format;
+pub mod library;
+pub mod lint;
 pub mod markov;
 pub mod parse;
+pub mod rename;
+pub mod render;
+pub mod renumber;
+pub mod search;
+pub mod stats;
+pub mod subject;
+pub mod syntax;
+pub mod write;
+pub mod zone;
+
+// ISO 8601 representations for dates and timestamps when (de)serializing
+#[cfg(feature = "serde")]
+time::serde::format_description!(iso_date, Date, "[year]-[month]-[day]");
+#[cfg(feature = "serde")]
+time::serde::format_description!(
+    iso_date_time,
+    PrimitiveDateTime,
+    "[year]-[month]-[day]T[hour]:[minute]:[second]"
+);
 
 /// A logbook of journal entries
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Logbook {
     /// The start position of this logbook
     start: Mark,
@@ -13,35 +43,108 @@ pub struct Logbook {
     end: Option<Mark>,
     /// The entries in this logbook
     entries: Vec<Entry>,
+    /// The pages the entries were written on, as read from the source text.
+    /// These are not updated when entries are added or removed, and are left
+    /// out when comparing logbooks
+    #[cfg_attr(feature = "serde", serde(default))]
+    pages: Vec<Page>,
+    /// The styles dates and times are written in, as read from the source
+    /// text
+    #[cfg_attr(feature = "serde", serde(default))]
+    style: Style,
+    /// The hour before which entries count for the previous day
+    #[cfg_attr(feature = "serde", serde(default = "default_next_day"))]
+    next_day: u8,
 }
 
 /// A position within a sequence of entries
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mark {
     /// The date for which the entry was written
+    #[cfg_attr(feature = "serde", serde(with = "iso_date"))]
     effective_date: Date,
     /// The entry number of the entry
     entry_number: u32,
 }
 
 /// An entry in a logbook
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entry {
     /// The position of this entry
     position: Mark,
     /// The starting timestamp for this entry
+    #[cfg_attr(feature = "serde", serde(with = "iso_date_time"))]
     started: PrimitiveDateTime,
     /// The ending timestamp for this entry
+    #[cfg_attr(feature = "serde", serde(with = "iso_date_time"))]
     finished: PrimitiveDateTime,
+    /// The time zone this entry was started in, if known
+    #[cfg_attr(feature = "serde", serde(default))]
+    started_zone: Option<Zone>,
+    /// The time zone this entry was finished in, if known
+    #[cfg_attr(feature = "serde", serde(default))]
+    finished_zone: Option<Zone>,
     /// The body of this entry
     contents: Vec<Block>,
 }
 
+/// A physical page of a logbook
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Page {
+    /// The position of this page among the logbook's pages, starting from 0
+    index: usize,
+    /// The ranges given in this page's header. None if the header could not
+    /// be read
+    header: Option<PageHeader>,
+    /// The entries and parts of entries on this page, in order
+    entries: Vec<PageEntry>,
+}
+
+/// The entry number and recorded date ranges declared by a page header
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PageHeader {
+    /// The entry number of the first entry on the page
+    first_number: u32,
+    /// The entry number of the last entry on the page
+    last_number: u32,
+    /// The recorded date of the first entry on the page
+    #[cfg_attr(feature = "serde", serde(with = "iso_date"))]
+    first_date: Date,
+    /// The recorded date of the last entry on the page
+    #[cfg_attr(feature = "serde", serde(with = "iso_date"))]
+    last_date: Date,
+    /// The time zone of the entries started on the page, if given
+    #[cfg_attr(feature = "serde", serde(default))]
+    zone: Option<Zone>,
+}
+
+/// An entry, or part of an entry, appearing on a page
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PageEntry {
+    /// The entry number of the entry
+    entry_number: u32,
+    /// The recorded date of the entry
+    #[cfg_attr(feature = "serde", serde(with = "iso_date"))]
+    recorded_date: Date,
+    /// Whether this part continues the entry from the previous page
+    continued: bool,
+    /// Whether the entry is continued on the next page
+    continues: bool,
+}
+
 /// One block of an entry's body
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block {
     /// The subject of this block
     subject: String,
+    /// The subject of this block split into its levels
+    subject_path: SubjectPath,
     /// The main text of this block
     text: String,
 }
@@ -52,8 +155,282 @@ impl Logbook {
             start,
             end,
             entries,
+            pages: Vec::new(),
+            style: Style::default(),
+            next_day: dialect::NEXT_DAY,
+        }
+    }
+    pub fn start(&self) -> &Mark {
+        &self.start
+    }
+    pub fn set_start(&mut self, start: Mark) {
+        self.start = start;
+    }
+    pub fn end(&self) -> Option<&Mark> {
+        self.end.as_ref()
+    }
+    pub fn set_end(&mut self, end: Option<Mark>) {
+        self.end = end;
+    }
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+    /// Iterate over the entries in this logbook
+    pub fn iter(&self) -> std::slice::Iter<'_, Entry> {
+        self.entries.iter()
+    }
+    /// Get the number of entries in this logbook
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    /// Check if this logbook has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    /// Find the entry with a given entry number
+    pub fn entry(&self, entry_number: u32) -> Option<&Entry> {
+        self.entries
+            .iter()
+            .find(|entry| entry.position.entry_number == entry_number)
+    }
+    /// Find the entries written for a given effective date
+    pub fn entries_on(&self, effective_date: Date) -> impl Iterator<Item = &Entry> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.position.effective_date == effective_date)
+    }
+    /// Add an entry, placing it after all entries with a lower or equal entry
+    /// number
+    pub fn insert(&mut self, entry: Entry) {
+        let index = self.entries.partition_point(|existing| {
+            existing.position.entry_number <= entry.position.entry_number
+        });
+        self.entries.insert(index, entry);
+    }
+    /// Remove the entry with a given entry number, returning it if present
+    pub fn remove(&mut self, entry_number: u32) -> Option<Entry> {
+        let index = self
+            .entries
+            .iter()
+            .position(|entry| entry.position.entry_number == entry_number)?;
+        Some(self.entries.remove(index))
+    }
+    /// Keep only the entries matching a predicate
+    pub fn retain(&mut self, predicate: impl FnMut(&Entry) -> bool) {
+        self.entries.retain(predicate);
+    }
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+    /// Renumber the entries from the first one on or after a mark's effective
+    /// date, giving them consecutive entry numbers starting from the mark's.
+    /// The start and end entry numbers and the entries and header number
+    /// ranges of affected pages are updated to match
+    pub fn renumber(&mut self, from: &Mark) {
+        let Some(first) = self
+            .entries
+            .iter()
+            .position(|entry| entry.position.effective_date >= from.effective_date)
+        else {
+            return;
+        };
+        for (entry, number) in self.entries[first..].iter_mut().zip(from.entry_number..) {
+            entry.position.entry_number = number;
+        }
+        // Keep the start and end marks on the first and last entries
+        if first == 0 {
+            self.start.entry_number = from.entry_number;
+        }
+        if let (Some(end), Some(last)) = (&mut self.end, self.entries.last()) {
+            end.entry_number = last.position.entry_number;
+        }
+        // Pages list the parts of each entry in the same order as the entries
+        let mut index = None;
+        for page in &mut self.pages {
+            let mut renumbered = false;
+            for page_entry in &mut page.entries {
+                if !page_entry.continued {
+                    index = Some(index.map_or(0, |index| index + 1));
+                }
+                if let Some(entry) = index
+                    .filter(|index| *index >= first)
+                    .and_then(|index| self.entries.get(index))
+                {
+                    page_entry.entry_number = entry.position.entry_number;
+                    renumbered = true;
+                }
+            }
+            if let (true, Some(header), Some(first), Some(last)) = (
+                renumbered,
+                &mut page.header,
+                page.entries.first(),
+                page.entries.last(),
+            ) {
+                header.first_number = first.entry_number;
+                header.last_number = last.entry_number;
+            }
+        }
+    }
+    pub fn set_pages(&mut self, pages: Vec<Page>) {
+        self.pages = pages;
+    }
+    pub fn style(&self) -> Style {
+        self.style
+    }
+    pub fn set_style(&mut self, style: Style) {
+        self.style = style;
+    }
+    pub fn next_day(&self) -> u8 {
+        self.next_day
+    }
+    pub fn set_next_day(&mut self, next_day: u8) {
+        self.next_day = next_day;
+    }
+    /// Get the effective date of an entry started at a timestamp. Entries
+    /// started before the next-day hour count for the previous day
+    pub fn effective_date(&self, started: PrimitiveDateTime) -> Date {
+        effective_date(started, self.next_day)
+    }
+    /// Find the pages an entry was written on. An entry continued across a
+    /// page break appears on more than one page
+    pub fn pages_of(&self, entry_number: u32) -> impl Iterator<Item = &Page> {
+        self.pages
+            .iter()
+            .filter(move |page| page.contains(entry_number))
+    }
+}
+
+/// Get the effective date of an entry started at a timestamp, given the hour
+/// before which entries count for the previous day. Entries started during
+/// that hour count for the day they were started on
+pub(crate) fn effective_date(started: PrimitiveDateTime, next_day: u8) -> Date {
+    if started.hour() >= next_day {
+        started.date()
+    } else {
+        started.date() - Duration::DAY
+    }
+}
+
+/// Get the hour before which entries count for the previous day when none is
+/// given
+#[cfg(feature = "serde")]
+fn default_next_day() -> u8 {
+    dialect::NEXT_DAY
+}
+
+impl IntoIterator for Logbook {
+    type Item = Entry;
+    type IntoIter = std::vec::IntoIter<Entry>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+/// Logbooks are equal if they have the same entries, start and end, written in
+/// the same style. Their pages are left out, as they record how the source text
+/// was laid out rather than what it says, and go stale as entries are added or
+/// removed. Otherwise a logbook that was built or changed in code could never
+/// equal the logbook read back from its written form
+impl PartialEq for Logbook {
+    fn eq(&self, other: &Self) -> bool {
+        self.start == other.start
+            && self.end == other.end
+            && self.entries == other.entries
+            && self.style == other.style
+            && self.next_day == other.next_day
+    }
+}
+
+impl<'a> IntoIterator for &'a Logbook {
+    type Item = &'a Entry;
+    type IntoIter = std::slice::Iter<'a, Entry>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter()
+    }
+}
+
+impl Page {
+    pub fn new(index: usize, header: Option<PageHeader>, entries: Vec<PageEntry>) -> Self {
+        Self {
+            index,
+            header,
+            entries,
+        }
+    }
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn header(&self) -> Option<&PageHeader> {
+        self.header.as_ref()
+    }
+    pub fn set_header(&mut self, header: Option<PageHeader>) {
+        self.header = header;
+    }
+    pub fn entries(&self) -> &[PageEntry] {
+        &self.entries
+    }
+    pub fn entries_mut(&mut self) -> &mut Vec<PageEntry> {
+        &mut self.entries
+    }
+    /// Check if any part of an entry is on this page
+    pub fn contains(&self, entry_number: u32) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.entry_number == entry_number)
+    }
+}
+
+impl PageHeader {
+    pub fn new(first_number: u32, last_number: u32, first_date: Date, last_date: Date) -> Self {
+        Self {
+            first_number,
+            last_number,
+            first_date,
+            last_date,
+            zone: None,
+        }
+    }
+    pub fn first_number(&self) -> u32 {
+        self.first_number
+    }
+    pub fn last_number(&self) -> u32 {
+        self.last_number
+    }
+    pub fn first_date(&self) -> Date {
+        self.first_date
+    }
+    pub fn last_date(&self) -> Date {
+        self.last_date
+    }
+    pub fn zone(&self) -> Option<Zone> {
+        self.zone
+    }
+    pub fn set_zone(&mut self, zone: Option<Zone>) {
+        self.zone = zone;
+    }
+}
+
+impl PageEntry {
+    pub fn new(entry_number: u32, recorded_date: Date, continued: bool, continues: bool) -> Self {
+        Self {
+            entry_number,
+            recorded_date,
+            continued,
+            continues,
         }
     }
+    pub fn entry_number(&self) -> u32 {
+        self.entry_number
+    }
+    pub fn recorded_date(&self) -> Date {
+        self.recorded_date
+    }
+    pub fn continued(&self) -> bool {
+        self.continued
+    }
+    pub fn continues(&self) -> bool {
+        self.continues
+    }
 }
 
 impl Mark {
@@ -82,6 +459,8 @@ impl Entry {
             position,
             started,
             finished,
+            started_zone: None,
+            finished_zone: None,
             contents,
         }
     }
@@ -100,6 +479,33 @@ impl Entry {
     pub fn finished(&self) -> PrimitiveDateTime {
         self.finished
     }
+    pub fn started_zone(&self) -> Option<Zone> {
+        self.started_zone
+    }
+    pub fn set_started_zone(&mut self, zone: Option<Zone>) {
+        self.started_zone = zone;
+    }
+    pub fn finished_zone(&self) -> Option<Zone> {
+        self.finished_zone
+    }
+    pub fn set_finished_zone(&mut self, zone: Option<Zone>) {
+        self.finished_zone = zone;
+    }
+    /// Get the starting timestamp with its offset from UTC. Entries without a
+    /// time zone are taken to be in UTC
+    pub fn started_at(&self) -> OffsetDateTime {
+        zone::assume(self.started, self.started_zone)
+    }
+    /// Get the ending timestamp with its offset from UTC. Entries without a
+    /// time zone are taken to be in UTC
+    pub fn finished_at(&self) -> OffsetDateTime {
+        zone::assume(self.finished, self.finished_zone)
+    }
+    /// Get how long this entry took to write, accounting for changes of time
+    /// zone and daylight saving time
+    pub fn duration(&self) -> Duration {
+        self.finished_at() - self.started_at()
+    }
     pub fn contents(&self) -> &Vec<Block> {
         &self.contents
     }
@@ -110,19 +516,39 @@ impl Entry {
     pub fn recorded_date(&self) -> Date {
         self.started.date()
     }
+    /// Count the words of text in this entry, not including subjects
+    pub fn word_count(&self) -> usize {
+        self.contents.iter().map(Block::word_count).sum()
+    }
 }
 
 impl Block {
     pub fn new(subject: String, text: String) -> Self {
-        Self { subject, text }
+        Self {
+            subject_path: SubjectPath::parse(&subject),
+            subject,
+            text,
+        }
     }
     pub fn subject(&self) -> &String {
         &self.subject
     }
+    pub fn set_subject(&mut self, subject: String) {
+        self.subject_path = SubjectPath::parse(&subject);
+        self.subject = subject;
+    }
     pub fn text(&self) -> &String {
         &self.text
     }
     pub fn text_mut(&mut self) -> &mut String {
         &mut self.text
     }
+    /// Get the subject of this block split into its levels
+    pub fn subject_path(&self) -> &SubjectPath {
+        &self.subject_path
+    }
+    /// Count the words of text in this block, not including its subject
+    pub fn word_count(&self) -> usize {
+        self.text.split_whitespace().count()
+    }
 }
@@ -0,0 +1,198 @@
+use crate::diagnostic::{Code, Diagnostic, Span};
+use crate::{Logbook, Mark};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use time::{Date, Duration};
+
+/// Successive volumes of one journal, each a separate logbook, ordered by
+/// their start marks
+#[derive(Debug, Default)]
+pub struct Library {
+    /// The volumes in this library
+    volumes: Vec<Volume>,
+}
+
+/// One volume of a library
+#[derive(Debug)]
+pub struct Volume {
+    /// The logbook making up this volume
+    logbook: Logbook,
+    /// The file the logbook was read from, if any
+    file: Option<PathBuf>,
+    /// The span of the entry range in the file's preamble, if known
+    range: Option<Span>,
+}
+
+impl Library {
+    pub fn new(volumes: Vec<Volume>) -> Self {
+        let mut library = Self::default();
+        for volume in volumes {
+            library.add(volume);
+        }
+        library
+    }
+    pub fn volumes(&self) -> &[Volume] {
+        &self.volumes
+    }
+    /// Add a volume, placing it after all volumes with an earlier or equal
+    /// start mark
+    pub fn add(&mut self, volume: Volume) {
+        let key = start_key(volume.logbook.start());
+        let index = self
+            .volumes
+            .partition_point(|existing| start_key(existing.logbook.start()) <= key);
+        self.volumes.insert(index, volume);
+    }
+    /// Check that each volume carries on where the previous one ended,
+    /// reporting gaps, overlaps, entry numbers used in more than one volume,
+    /// and open ends on volumes other than the last
+    pub fn check(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        for pair in self.volumes.windows(2) {
+            let (previous, next) = (&pair[0], &pair[1]);
+            // Only the last volume may be left open, otherwise carry on from
+            // its last entry
+            let end = match previous.logbook.end() {
+                Some(end) => Some(end.clone()),
+                None => {
+                    let last = previous
+                        .logbook
+                        .entries()
+                        .last()
+                        .map(|entry| entry.position().clone());
+                    let diagnostic = Diagnostic::error(
+                        Code::OpenVolume,
+                        "volume has an open end but is followed by another volume",
+                    )
+                    .with_note(format!("followed by {}", next.name()));
+                    diagnostics.push(previous.locate(match &last {
+                        Some(last) => diagnostic.with_mark(last.clone()),
+                        None => diagnostic,
+                    }));
+                    last
+                }
+            };
+            let Some(end) = end else {
+                continue;
+            };
+            // Check that the next volume starts just after the end
+            let expected = Mark::new(end.effective_date() + Duration::DAY, end.entry_number() + 1);
+            let start = next.logbook.start();
+            if start_key(start) != start_key(&expected) {
+                let (code, problem) = if start_key(start) > start_key(&expected) {
+                    (Code::VolumeGap, "entries are missing between volumes")
+                } else {
+                    (
+                        Code::VolumeOverlap,
+                        "volume starts before the previous one ends",
+                    )
+                };
+                diagnostics.push(
+                    next.locate(
+                        Diagnostic::error(
+                            code,
+                            format!(
+                                "{problem}: expected a start of {} {} but got {} {}",
+                                expected.effective_date(),
+                                expected.entry_number(),
+                                start.effective_date(),
+                                start.entry_number(),
+                            ),
+                        )
+                        .with_mark(start.clone()),
+                    )
+                    .with_note(format!("previous volume is {}", previous.name())),
+                );
+            }
+        }
+        // Check that no entry number is used in more than one volume
+        let numbers = self
+            .volumes
+            .iter()
+            .map(|volume| {
+                volume
+                    .logbook
+                    .iter()
+                    .map(|entry| entry.position().entry_number())
+                    .collect::<BTreeSet<_>>()
+            })
+            .collect::<Vec<_>>();
+        for (index, volume) in self.volumes.iter().enumerate() {
+            for (other, other_numbers) in self.volumes.iter().zip(&numbers).take(index) {
+                let shared = numbers[index]
+                    .intersection(other_numbers)
+                    .collect::<Vec<_>>();
+                if let (Some(first), Some(last)) = (shared.first(), shared.last()) {
+                    let diagnostic = Diagnostic::error(
+                        Code::DuplicateEntry,
+                        format!(
+                            "{} entry number(s) from {first} to {last} also appear in {}",
+                            shared.len(),
+                            other.name(),
+                        ),
+                    );
+                    let mark = volume
+                        .logbook
+                        .iter()
+                        .find(|entry| entry.position().entry_number() == **first)
+                        .map(|entry| entry.position().clone());
+                    diagnostics.push(volume.locate(match mark {
+                        Some(mark) => diagnostic.with_mark(mark),
+                        None => diagnostic,
+                    }));
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+impl Volume {
+    pub fn new(logbook: Logbook, file: Option<PathBuf>) -> Self {
+        Self {
+            logbook,
+            file,
+            range: None,
+        }
+    }
+    pub fn logbook(&self) -> &Logbook {
+        &self.logbook
+    }
+    pub fn file(&self) -> Option<&Path> {
+        self.file.as_deref()
+    }
+    pub fn range(&self) -> Option<Span> {
+        self.range
+    }
+    pub fn set_range(&mut self, range: Option<Span>) {
+        self.range = range;
+    }
+    /// Get a name for this volume to use in messages
+    pub fn name(&self) -> String {
+        match &self.file {
+            Some(file) => file.display().to_string(),
+            None => format!(
+                "the volume starting {} {}",
+                self.logbook.start().effective_date(),
+                self.logbook.start().entry_number()
+            ),
+        }
+    }
+    /// Attach this volume's file and entry range to a diagnostic, where
+    /// known
+    fn locate(&self, diagnostic: Diagnostic) -> Diagnostic {
+        let diagnostic = match &self.file {
+            Some(file) => diagnostic.with_file(file),
+            None => diagnostic,
+        };
+        match self.range {
+            Some(range) => diagnostic.with_span(range),
+            None => diagnostic,
+        }
+    }
+}
+
+/// Get a key ordering marks by entry number, then by effective date
+fn start_key(mark: &Mark) -> (u32, Date) {
+    (mark.entry_number(), mark.effective_date())
+}
@@ -0,0 +1,330 @@
+use crate::diagnostic::{Code, Diagnostic, Span};
+use crate::parse;
+use crate::syntax::SyntaxTree;
+use crate::zone::{Zone, MAX_OFFSET_HOURS};
+use crate::{Entry, Logbook};
+use std::collections::HashMap;
+use time::{Duration, OffsetDateTime, Time};
+
+/// Longest an entry may take by default before it is reported
+const MAX_DURATION: Duration = Duration::hours(3);
+
+/// Furthest an entry's recorded date may be from the day after the previous
+/// entry's effective date by default before it is reported
+const MAX_DRIFT: Duration = Duration::DAY;
+
+/// Furthest ahead of UTC a time zone may be. Entries without a zone are only
+/// reported as being in the future once they are in every zone
+const ZONELESS_LEEWAY: Duration = Duration::hours(MAX_OFFSET_HOURS as i64);
+
+/// Shortest an entry finishing the day after it was started may appear to
+/// take before it is taken to have been finished before it was started on the
+/// same day
+const BACKWARDS_DURATION: Duration = Duration::hours(12);
+
+/// A sanity check of the times of a logbook's entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    /// An entry looks to have been finished earlier on the day it was started
+    FinishedBeforeStarted,
+    /// An entry took longer than the maximum duration
+    LongEntry,
+    /// An entry was finished at the same time it was started
+    ZeroLength,
+    /// An entry was started before the previous entry was finished
+    OverlappingEntries,
+    /// An entry was recorded far from the day after the previous entry
+    DateDrift,
+    /// An entry was started after the current time
+    FutureEntry,
+}
+
+/// Which rules to check the times of entries with, and the limits they use
+#[derive(Debug, Clone)]
+pub struct Lints {
+    /// The rules to check, in the order they were enabled
+    enabled: Vec<Rule>,
+    /// Longest an entry may take before it is reported
+    max_duration: Duration,
+    /// Furthest an entry's recorded date may be from the day after the
+    /// previous entry's effective date
+    max_drift: Duration,
+    /// The current time, after which entries are in the future
+    now: OffsetDateTime,
+}
+
+impl Rule {
+    /// Every rule, in the order they are checked
+    pub const ALL: [Rule; 6] = [
+        Rule::FinishedBeforeStarted,
+        Rule::LongEntry,
+        Rule::ZeroLength,
+        Rule::OverlappingEntries,
+        Rule::DateDrift,
+        Rule::FutureEntry,
+    ];
+
+    /// Get the code of the diagnostics this rule reports
+    pub fn code(self) -> Code {
+        match self {
+            Rule::FinishedBeforeStarted => Code::FinishedBeforeStarted,
+            Rule::LongEntry => Code::LongEntry,
+            Rule::ZeroLength => Code::ZeroLength,
+            Rule::OverlappingEntries => Code::OverlappingEntries,
+            Rule::DateDrift => Code::DateDrift,
+            Rule::FutureEntry => Code::FutureEntry,
+        }
+    }
+
+    /// Get the name of this rule, which is the same as its code
+    pub fn name(self) -> &'static str {
+        self.code().as_str()
+    }
+
+    /// Find a rule by name
+    pub fn from_name(name: &str) -> Option<Rule> {
+        Rule::ALL.into_iter().find(|rule| rule.name() == name)
+    }
+}
+
+impl Default for Lints {
+    fn default() -> Self {
+        Self {
+            enabled: Vec::new(),
+            max_duration: MAX_DURATION,
+            max_drift: MAX_DRIFT,
+            now: OffsetDateTime::now_utc(),
+        }
+    }
+}
+
+impl Lints {
+    /// Create a set of lints with no rules enabled and the default limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Create a set of lints with every rule enabled and the default limits
+    pub fn all() -> Self {
+        let mut lints = Self::default();
+        for rule in Rule::ALL {
+            lints.enable(rule);
+        }
+        lints
+    }
+    pub fn enabled(&self) -> &[Rule] {
+        &self.enabled
+    }
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        self.enabled.contains(&rule)
+    }
+    pub fn enable(&mut self, rule: Rule) {
+        if !self.is_enabled(rule) {
+            self.enabled.push(rule);
+        }
+    }
+    pub fn disable(&mut self, rule: Rule) {
+        self.enabled.retain(|enabled| *enabled != rule);
+    }
+    pub fn max_duration(&self) -> Duration {
+        self.max_duration
+    }
+    pub fn set_max_duration(&mut self, max_duration: Duration) {
+        self.max_duration = max_duration;
+    }
+    pub fn max_drift(&self) -> Duration {
+        self.max_drift
+    }
+    pub fn set_max_drift(&mut self, max_drift: Duration) {
+        self.max_drift = max_drift;
+    }
+    pub fn now(&self) -> OffsetDateTime {
+        self.now
+    }
+    pub fn set_now(&mut self, now: OffsetDateTime) {
+        self.now = now;
+    }
+
+    /// Check the entries of a logbook with the enabled rules, reporting
+    /// problems as warnings related to their entries
+    pub fn check(&self, logbook: &Logbook) -> Vec<Diagnostic> {
+        self.check_entries(logbook, &HashMap::new())
+    }
+
+    /// Check the entries of a logbook parsed from a syntax tree in the same way
+    /// as [`Lints::check`], pointing each problem at its entry's header
+    pub fn check_tree(&self, tree: &SyntaxTree, logbook: &Logbook) -> Vec<Diagnostic> {
+        let mut headers = HashMap::new();
+        for header in tree
+            .pages()
+            .iter()
+            .flat_map(|page| page.entries())
+            .filter_map(|entry| entry.header())
+        {
+            if let Some(values) = parse::read_entry_header(tree.text(header.span()), tree.dialect())
+            {
+                headers.entry(values.number).or_insert(header.span());
+            }
+        }
+        self.check_entries(logbook, &headers)
+            .into_iter()
+            .map(|diagnostic| diagnostic.locate(tree.source()))
+            .collect()
+    }
+
+    /// Check the entries of a logbook, given the spans of their headers by
+    /// entry number where known
+    fn check_entries(&self, logbook: &Logbook, headers: &HashMap<u32, Span>) -> Vec<Diagnostic> {
+        let warning = |rule: Rule, entry: &Entry, message: String| {
+            let number = entry.position().entry_number();
+            let diagnostic =
+                Diagnostic::warning(rule.code(), message).with_mark(entry.position().clone());
+            match headers.get(&number) {
+                Some(span) => diagnostic.with_span(*span),
+                None => diagnostic,
+            }
+        };
+        let mut diagnostics = Vec::new();
+        let mut previous: Option<&Entry> = None;
+        for entry in logbook {
+            let duration = entry.duration();
+            for rule in Rule::ALL.into_iter().filter(|rule| self.is_enabled(*rule)) {
+                let diagnostic = match rule {
+                    Rule::FinishedBeforeStarted if backwards(entry) => Some(warning(
+                        rule,
+                        entry,
+                        format!(
+                            "entry looks to have been finished at {} before it was started at {}",
+                            clock(entry.finished().time()),
+                            clock(entry.started().time()),
+                        ),
+                    )),
+                    Rule::LongEntry
+                        if duration > self.max_duration
+                            && !backwards(entry)
+                            && !zero_length(entry) =>
+                    {
+                        Some(warning(
+                            rule,
+                            entry,
+                            format!(
+                                "entry took {duration}, longer than the maximum of {}",
+                                self.max_duration
+                            ),
+                        ))
+                    }
+                    Rule::ZeroLength if zero_length(entry) => Some(warning(
+                        rule,
+                        entry,
+                        format!(
+                            "entry was finished at the same time it was started, {}",
+                            clock(entry.started().time())
+                        ),
+                    )),
+                    Rule::OverlappingEntries => previous
+                        // Entries with implausible times are reported by
+                        // other rules instead
+                        .filter(|previous| !backwards(previous) && !zero_length(previous))
+                        .filter(|previous| entry.started_at() < previous.finished_at())
+                        .map(|previous| {
+                            let diagnostic = warning(
+                                rule,
+                                entry,
+                                format!(
+                                    "entry was started at {} before the previous entry was finished at {}",
+                                    moment(entry.started_at()),
+                                    moment(previous.finished_at()),
+                                ),
+                            );
+                            match headers.get(&previous.position().entry_number()) {
+                                Some(span) => diagnostic.with_label(*span, "previous entry"),
+                                None => diagnostic,
+                            }
+                        }),
+                    // An entry is expected the day after the previous one,
+                    // and may be recorded past midnight into the next day
+                    Rule::DateDrift => previous
+                        .map(|previous| {
+                            let expected = previous.position().effective_date() + Duration::DAY;
+                            (previous, expected, (entry.recorded_date() - expected).abs())
+                        })
+                        .filter(|(_, _, drift)| *drift > self.max_drift)
+                        .map(|(previous, expected, drift)| {
+                            let diagnostic = warning(
+                                rule,
+                                entry,
+                                format!(
+                                    "entry was recorded on {}, {} day(s) from {}, the day after the previous entry",
+                                    entry.recorded_date(),
+                                    drift.whole_days(),
+                                    expected,
+                                ),
+                            );
+                            match headers.get(&previous.position().entry_number()) {
+                                Some(span) => diagnostic.with_label(*span, "previous entry"),
+                                None => diagnostic,
+                            }
+                        }),
+                    Rule::FutureEntry if self.in_future(entry) => Some(warning(
+                        rule,
+                        entry,
+                        match entry.started_zone() {
+                            Some(_) => format!(
+                                "entry was started in the future, at {}",
+                                moment(entry.started_at())
+                            ),
+                            None => format!(
+                                "entry was started in the future, at {} {}",
+                                entry.started().date(),
+                                clock(entry.started().time())
+                            ),
+                        },
+                    )),
+                    _ => None,
+                };
+                diagnostics.extend(diagnostic);
+            }
+            previous = Some(entry);
+        }
+        diagnostics
+    }
+
+    /// Check if an entry was started after the current time. Entries without
+    /// a time zone could have been written in any zone, so they are only taken
+    /// to be in the future once they are in every zone
+    fn in_future(&self, entry: &Entry) -> bool {
+        match entry.started_zone() {
+            Some(_) => entry.started_at() > self.now,
+            None => entry.started_at() - ZONELESS_LEEWAY > self.now,
+        }
+    }
+}
+
+/// Check if an entry looks to have been finished before it was started on the
+/// same day, having been taken to finish on the next day instead
+fn backwards(entry: &Entry) -> bool {
+    entry.finished().date() > entry.started().date()
+        && entry.duration() > BACKWARDS_DURATION
+        && !zero_length(entry)
+}
+
+/// Check if an entry was finished at the same time it was started. Such
+/// entries are taken to finish on the next day when parsed
+fn zero_length(entry: &Entry) -> bool {
+    let elapsed = entry.finished() - entry.started();
+    elapsed.is_zero() || elapsed == Duration::DAY
+}
+
+/// Show a time of day in messages
+fn clock(time: Time) -> String {
+    format!("{:02}:{:02}", time.hour(), time.minute())
+}
+
+/// Show a timestamp and its offset from UTC in messages
+fn moment(at: OffsetDateTime) -> String {
+    format!(
+        "{} {} {}",
+        at.date(),
+        clock(at.time()),
+        Zone::Fixed(at.offset())
+    )
+}
@@ -1,79 +1,837 @@
-use logbook_integrity::{markov, parse};
+use glob::Pattern;
+use logbook_integrity::calendar::Calendar;
+use logbook_integrity::diagnostic::{Code, Diagnostic, Severity};
+use logbook_integrity::dialect::Dialect;
+use logbook_integrity::library::{Library, Volume};
+use logbook_integrity::lint::{Lints, Rule};
+use logbook_integrity::parse::ParseOutcome;
+use logbook_integrity::search::{Query, Search};
+use logbook_integrity::stats::{self, Grouping};
+use logbook_integrity::subject::{Aliases, SubjectPath, SubjectTree};
+use logbook_integrity::syntax::SyntaxTree;
+use logbook_integrity::{fix, format, markov, parse, rename, render, renumber, Logbook, Mark};
+use similar::TextDiff;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use time::Duration;
+
+/// Pattern for the names of files to read when recursing into directories, if
+/// no other patterns are given
+const DEFAULT_INCLUDE: &str = "*.txt";
+
+/// Exit code for when no problems were found
+const EXIT_CLEAN: i32 = 0;
+
+/// Exit code for when only warnings were found
+const EXIT_WARNINGS: i32 = 1;
+
+/// Exit code for when errors were found
+const EXIT_ERRORS: i32 = 2;
+
+/// Exit code for when some input could not be read
+const EXIT_UNREADABLE: i32 = 3;
+
+/// Exit code for when the options or arguments could not be understood
+const EXIT_USAGE: i32 = 4;
+
+/// Exit code for when checking formatting or fixes found files that would be
+/// changed
+const EXIT_UNFORMATTED: i32 = 1;
 
 fn main() {
     match run(&mut std::env::args().skip(1)) {
-        Ok(_) => (),
-        Err(message) => {
+        Ok(code) => std::process::exit(code),
+        Err(Failure::Usage(message)) => {
             eprintln!("Error: {message}");
-            std::process::exit(1);
+            std::process::exit(EXIT_USAGE);
+        }
+        Err(Failure::Unreadable(message)) => {
+            eprintln!("Error: {message}");
+            std::process::exit(EXIT_UNREADABLE);
         }
     }
 }
 
-fn run(args: &mut impl Iterator<Item = String>) -> Result<(), String> {
+/// Run the program, returning the exit code to use
+fn run(args: &mut impl Iterator<Item = String>) -> Result<i32, Failure> {
     // Get action to perform
     let action = args.next();
 
-    // Get input paths
-    let paths = expand_paths(args.map(PathBuf::from).collect())?;
+    // Get options and input paths, the first of which is the query to search
+    // for when searching
+    let (options, mut paths) = parse_options(args)?;
+    let query = match action.as_deref() {
+        Some("search") if !paths.is_empty() => paths.remove(0).into_os_string().into_string().ok(),
+        _ => None,
+    };
+    let paths = expand_paths(paths, &options).map_err(Failure::Unreadable)?;
 
     // Perform action
     match action.as_deref() {
-        Some("help") => help(),
-        Some("validate") => {
-            let _ = parse::parse_files(paths)?;
-            Ok(())
+        Some("help") => Ok(help()),
+        Some("validate") => Ok(validate(&paths, &options)),
+        Some("fmt") => Ok(rewrite_files(
+            &paths,
+            &options,
+            &|text| format::format(text, &options.dialect),
+            "Formatted",
+            "would be reformatted",
+        )),
+        Some("fix") => Ok(rewrite_files(
+            &paths,
+            &options,
+            &|text| fix::fix_page_headers(text, &options.dialect),
+            "Fixed page headers in",
+            "would have page headers fixed",
+        )),
+        Some("renumber") => {
+            let (Some(date), Some(number)) = (&options.date, options.number) else {
+                return Err(Failure::Usage(
+                    "renumber needs --date and --number".to_owned(),
+                ));
+            };
+            let date = parse::read_date(date, &options.dialect)
+                .map_err(|error| format!("Bad date {date}: {}", error.message()))?;
+            let from = Mark::new(date, number);
+            Ok(rewrite_files(
+                &paths,
+                &options,
+                &|text| renumber::renumber(text, &from, &options.dialect),
+                "Renumbered entries in",
+                "would have entries renumbered",
+            ))
+        }
+        Some("rename-subject") => {
+            if options.aliases.is_empty() {
+                return Err(Failure::Usage(
+                    "rename-subject needs --aliases or --alias".to_owned(),
+                ));
+            }
+            Ok(rewrite_files(
+                &paths,
+                &options,
+                &|text| rename::rename_subjects(text, &options.aliases, &options.dialect),
+                "Renamed subjects in",
+                "would have subjects renamed",
+            ))
         }
         Some("markov") => {
-            let parsed = parse::parse_files(paths)?;
-            let chain = markov::chain_from_logs(&parsed);
+            let logbooks = read_logbooks(paths, &options)?;
+            let chain = markov::chain_from_logs(&logbooks);
             let generated = chain.generate(["", ""]);
             println!("{}", generated);
-            Ok(())
+            Ok(EXIT_CLEAN)
+        }
+        Some("stats") => {
+            let logbooks = read_logbooks(paths, &options)?;
+            let groups = stats::stats(&logbooks, options.grouping)
+                .into_iter()
+                .map(|group| group.to_string())
+                .collect::<Vec<_>>();
+            print!("{}", groups.join("\n"));
+            Ok(EXIT_CLEAN)
+        }
+        Some("subjects") => {
+            let logbooks = read_logbooks(paths, &options)?;
+            let tree = SubjectTree::new(&logbooks);
+            match &options.subject {
+                Some(subject) => match tree.find(subject) {
+                    Some(node) => print!("{node}"),
+                    None => println!("No blocks have the subject {subject}"),
+                },
+                None => print!("{tree}"),
+            }
+            Ok(EXIT_CLEAN)
+        }
+        Some("search") => {
+            let Some(query) = query else {
+                return Err(Failure::Usage("search needs a query".to_owned()));
+            };
+            let query = if options.regex {
+                Query::regex(&query, options.ignore_case)
+            } else {
+                Query::words(&query, options.ignore_case)
+            }
+            .map_err(|error| format!("Bad query {query}: {error}"))?;
+            let date = |date: &Option<String>| {
+                date.as_ref()
+                    .map(|date| {
+                        parse::read_date(date, &options.dialect)
+                            .map_err(|error| format!("Bad date {date}: {}", error.message()))
+                    })
+                    .transpose()
+            };
+            let mut search = Search::new(query);
+            search.set_subject(options.subject.clone());
+            search.set_from(date(&options.from)?);
+            search.set_to(date(&options.to)?);
+            let logbooks = read_logbooks(paths, &options)?;
+            let hits = search.find(&logbooks);
+            let rendered = hits
+                .iter()
+                .map(|hit| hit.render(options.color.enabled()))
+                .collect::<Vec<_>>();
+            print!("{}", rendered.join("\n"));
+            if hits.is_empty() {
+                println!("No blocks match");
+            }
+            Ok(EXIT_CLEAN)
+        }
+        Some("calendar") => {
+            let logbooks = read_logbooks(paths, &options)?;
+            let calendar = Calendar::new(&logbooks);
+            let years = match options.year {
+                Some(year) => vec![year],
+                None => calendar.years().into_iter().collect(),
+            };
+            let rendered = years
+                .into_iter()
+                .map(|year| calendar.render(year, options.color.enabled()))
+                .collect::<Vec<_>>();
+            print!("{}", rendered.join("\n"));
+            Ok(EXIT_CLEAN)
         }
         None => {
             println!("No action given");
-            help()
+            help();
+            Ok(EXIT_USAGE)
         }
         _ => {
             println!("Unrecognized action");
-            help()
+            help();
+            Ok(EXIT_USAGE)
         }
     }
 }
 
-/// Print the help message
-fn help() -> Result<(), String> {
+/// Print the help message, returning the exit code for having done so
+fn help() -> i32 {
     println!("logbook-integrity v{}", env!("CARGO_PKG_VERSION"));
-    println!("Usage: logbook-integrity <action> [paths...]");
+    println!("Usage: logbook-integrity <action> [options] [paths...]");
+    println!("       logbook-integrity search <query> [options] [paths...]");
     println!("Actions:");
     println!("    help - print this message");
     println!("    validate - read in the logbook files, checking them for metadata errors");
+    println!("        (with several files, also check that they carry on from each other)");
     println!("    markov - use the logbook files to create a Markov chain, and generate some text");
-    Ok(())
+    println!("    stats - report statistics about the entries of the logbook files");
+    println!("    subjects - show the subjects of the logbook files as a tree, with how many");
+    println!("        blocks have each and the first and last entries they appear in");
+    println!("    search - show the blocks whose text has every word of a query, with the");
+    println!("        words highlighted. Words in double quotes must appear together");
+    println!("    calendar - show a calendar of the words written for each day, marking");
+    println!("        days missing an entry");
+    println!("    fmt - rewrite the logbook files into canonical form");
+    println!("    fix - recompute the page headers of the logbook files from their entries");
+    println!("    renumber - renumber entries from a date onwards, starting from a number");
+    println!("    rename-subject - rewrite subject lines with an alias to their canonical subject");
+    println!("Options:");
+    println!("    --color=<auto|always|never> - when to colorize diagnostics");
+    println!("    --format=<human|json|jsonl> - how to output diagnostics");
+    println!("    --include=<pattern> - read files with matching names found in directories");
+    println!("        (may be repeated, defaults to {DEFAULT_INCLUDE})");
+    println!("    --exclude=<pattern> - skip files with matching names found in directories");
+    println!("        (may be repeated)");
+    println!("    --dialect=<file> - read the words and formats the logbooks are written with");
+    println!("        from a configuration file");
+    println!("    --next-day=<hour> - count entries started before this hour (0 to 23) for the");
+    println!("        previous day, unless a logbook declares its own");
+    println!("    --aliases=<file> - read aliases mapping old subjects to canonical ones, which");
+    println!("        are used by stats, subjects, search, markov and rename-subject");
+    println!("    --alias=<old>=<canonical> - alias an old subject to a canonical one");
+    println!("        (may be repeated)");
+    println!("    --volumes - (validate) check that the files are volumes of one journal,");
+    println!("        each carrying on from the previous one");
+    println!("    --lint=<rule|all> - (validate) also check the times of entries with a rule");
+    println!("        (may be repeated), one of:");
+    for rule in Rule::ALL {
+        println!("        {}", rule.name());
+    }
+    println!("    --max-duration=<minutes> - (validate) longest an entry may take for the");
+    println!("        long-entry rule (defaults to 180)");
+    println!("    --max-drift=<days> - (validate) furthest an entry's recorded date may be from");
+    println!("        the day after the previous entry for the date-drift rule (defaults to 1)");
+    println!("    --by=<month|year> - (stats) report statistics for each month or year");
+    println!("    --year=<year> - (calendar) only show this year");
+    println!("    --subject=<subject> - (subjects, search) only show this subject and those");
+    println!("        within it");
+    println!("    --ignore-case - (search) match regardless of case");
+    println!("    --regex - (search) treat the query as a regular expression");
+    println!("    --from=<date> - (search) only search entries for this effective date or later");
+    println!("    --to=<date> - (search) only search entries for this effective date or earlier");
+    println!("    --check - (fmt, fix, renumber, rename-subject) list files that would be");
+    println!("        changed instead");
+    println!("    --diff - (fmt, fix, renumber, rename-subject) show the changes that would be");
+    println!("        made instead");
+    println!("    --dry-run - (fix, renumber, rename-subject) same as --diff");
+    println!("    --date=<date> - (renumber) effective date of the first entry to renumber");
+    println!("    --number=<number> - (renumber) new entry number of that entry");
+    println!("Exit codes (validate):");
+    println!("    {EXIT_CLEAN} - no problems found");
+    println!("    {EXIT_WARNINGS} - only warnings found");
+    println!("    {EXIT_ERRORS} - errors found");
+    println!("    {EXIT_UNREADABLE} - some input could not be read");
+    println!("Exit codes (fmt, fix, renumber, rename-subject):");
+    println!("    {EXIT_CLEAN} - no files needed changes, or all changes were made");
+    println!("    {EXIT_UNFORMATTED} - with --check, --diff or --dry-run, files would change");
+    println!("    {EXIT_ERRORS} - some files could not be parsed");
+    println!("    {EXIT_UNREADABLE} - some files could not be read or written");
+    println!("Exit codes (any action):");
+    println!("    {EXIT_USAGE} - the action, options or arguments could not be understood");
+    EXIT_CLEAN
+}
+
+/// Validate each file, outputting its diagnostics in the chosen format, and
+/// return the exit code for the most serious problem found
+fn validate(paths: &[PathBuf], options: &Options) -> i32 {
+    let mut code = EXIT_CLEAN;
+    let mut json = Vec::new();
+    let mut volumes = Vec::new();
+    let mut sources = HashMap::new();
+    for path in paths {
+        let (source, logbook, diagnostics) = validate_file(path, options);
+        output(
+            &path.display().to_string(),
+            &|_| source.as_deref(),
+            &diagnostics,
+            options,
+            &mut json,
+        );
+        code = code.max(exit_code(&diagnostics));
+        if !options.volumes {
+            continue;
+        }
+        if let (Some(source), Some(logbook)) = (source, logbook) {
+            let mut volume = Volume::new(logbook, Some(path.clone()));
+            let tree = SyntaxTree::with_dialect(&source, &options.dialect);
+            volume.set_range(tree.preamble().entry_range().map(|range| range.span()));
+            volumes.push(volume);
+            sources.insert(path.clone(), source);
+        }
+    }
+    // Check that the files carry on from each other
+    if volumes.len() > 1 {
+        let source = |diagnostic: &Diagnostic| {
+            diagnostic
+                .file()
+                .and_then(|file| sources.get(file))
+                .map(String::as_str)
+        };
+        let diagnostics = Library::new(volumes)
+            .check()
+            .into_iter()
+            .map(|diagnostic| match source(&diagnostic) {
+                Some(text) => diagnostic.locate(text),
+                None => diagnostic,
+            })
+            .collect::<Vec<_>>();
+        output("volumes", &source, &diagnostics, options, &mut json);
+        code = code.max(exit_code(&diagnostics));
+    }
+    if let OutputFormat::Json = options.format {
+        println!("[{}]", json.join(","));
+    }
+    code
 }
 
-/// Expand a series of paths by recursing into directories
-fn expand_paths(paths: Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
-    paths
+/// Rewrite each file (or, with --check, --diff or --dry-run, report the files
+/// that would change) and return the exit code. The messages describe a
+/// rewritten file, and one that would be rewritten
+fn rewrite_files(
+    paths: &[PathBuf],
+    options: &Options,
+    rewrite: &dyn Fn(&str) -> Result<String, Diagnostic>,
+    done: &str,
+    pending: &str,
+) -> i32 {
+    let color = options.color.enabled();
+    let mut code = EXIT_CLEAN;
+    for path in paths {
+        let text = match parse::read_file(path) {
+            Ok(text) => text,
+            Err(error) => {
+                println!("{}", render::render(&error, None, color));
+                code = code.max(EXIT_UNREADABLE);
+                continue;
+            }
+        };
+        let rewritten = match rewrite(&text) {
+            Ok(rewritten) => rewritten,
+            Err(error) => {
+                let error = error.with_file(path);
+                println!("{}", render::render(&error, Some(&text), color));
+                code = code.max(EXIT_ERRORS);
+                continue;
+            }
+        };
+        if rewritten == text {
+            continue;
+        }
+        if options.diff || options.dry_run {
+            let name = path.display().to_string();
+            print!(
+                "{}",
+                TextDiff::from_lines(&text, &rewritten)
+                    .unified_diff()
+                    .header(&name, &name)
+            );
+            code = code.max(EXIT_UNFORMATTED);
+        } else if options.check {
+            println!("{} {pending}", path.display());
+            code = code.max(EXIT_UNFORMATTED);
+        } else if let Err(error) = fs::write(path, rewritten) {
+            eprintln!("Error: Could not write file {}: {}", path.display(), error);
+            code = code.max(EXIT_UNREADABLE);
+        } else {
+            println!("{done} {}", path.display());
+        }
+    }
+    code
+}
+
+/// Output the diagnostics for a file (or other named group of diagnostics) in
+/// the chosen format, showing each with the source text of its file.
+/// Diagnostics for the JSON format are collected to be output together at the
+/// end
+fn output<'a>(
+    name: &str,
+    source: &dyn Fn(&Diagnostic) -> Option<&'a str>,
+    diagnostics: &[Diagnostic],
+    options: &Options,
+    json: &mut Vec<String>,
+) {
+    match options.format {
+        OutputFormat::Human => {
+            for diagnostic in diagnostics {
+                println!(
+                    "{}",
+                    render::render(diagnostic, source(diagnostic), options.color.enabled())
+                );
+            }
+            let count = |severity| {
+                diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic.severity() == severity)
+                    .count()
+            };
+            println!(
+                "{name}: {} error(s), {} warning(s)",
+                count(Severity::Error),
+                count(Severity::Warning)
+            );
+        }
+        OutputFormat::Json => json.extend(diagnostics.iter().map(Diagnostic::to_json)),
+        OutputFormat::JsonLines => {
+            for diagnostic in diagnostics {
+                println!("{}", diagnostic.to_json());
+            }
+        }
+    }
+}
+
+/// Read and parse a single file, returning its text (if it could be read), the
+/// logbook (if it could be parsed) and the diagnostics found, including those
+/// of any enabled lint rules
+fn validate_file(
+    path: &Path,
+    options: &Options,
+) -> (Option<String>, Option<Logbook>, Vec<Diagnostic>) {
+    let text = match parse::read_file(path) {
+        Ok(text) => text,
+        Err(error) => return (None, None, vec![error]),
+    };
+    let tree = SyntaxTree::with_dialect(&text, &options.dialect);
+    let (logbook, diagnostics) = match parse::parse_tree(&tree) {
+        Ok(outcome) => {
+            let (logbook, mut diagnostics) = outcome.with_file(path).into_parts();
+            diagnostics.extend(
+                options
+                    .lints
+                    .check_tree(&tree, &logbook)
+                    .into_iter()
+                    .map(|diagnostic| diagnostic.with_file(path)),
+            );
+            (Some(logbook), diagnostics)
+        }
+        Err(error) => (None, vec![error.with_file(path)]),
+    };
+    (Some(text), logbook, diagnostics)
+}
+
+/// Get the exit code for the most serious of a set of diagnostics
+fn exit_code(diagnostics: &[Diagnostic]) -> i32 {
+    let has = |severity| {
+        diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity() == severity)
+    };
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.code() == Code::UnreadableFile)
+    {
+        EXIT_UNREADABLE
+    } else if has(Severity::Error) {
+        EXIT_ERRORS
+    } else if has(Severity::Warning) {
+        EXIT_WARNINGS
+    } else {
+        EXIT_CLEAN
+    }
+}
+
+/// Read and parse several files for an action that uses their entries,
+/// printing any diagnostics and giving aliased subjects their canonical forms
+fn read_logbooks(paths: Vec<PathBuf>, options: &Options) -> Result<Vec<Logbook>, Failure> {
+    let parsed = parse::parse_files(paths, &options.dialect)
+        .map_err(|error| Failure::Unreadable(error.to_string()))?;
+    report(&parsed);
+    let mut logbooks = parsed
         .into_iter()
-        .map(|path| match fs::metadata(&path) {
-            Ok(metadata) => {
-                if metadata.is_dir() {
-                    expand_paths(
-                        fs::read_dir(path)
-                            .unwrap()
-                            .map(|entry| entry.unwrap().path())
-                            .collect(),
-                    )
-                } else {
-                    Ok(vec![path])
+        .map(|outcome| outcome.into_parts().0)
+        .collect::<Vec<_>>();
+    options.aliases.apply(&mut logbooks);
+    Ok(logbooks)
+}
+
+/// Print the diagnostics found while parsing
+fn report(outcomes: &[ParseOutcome]) {
+    for diagnostic in outcomes.iter().flat_map(ParseOutcome::diagnostics) {
+        eprintln!("{diagnostic}\n");
+    }
+}
+
+/// Why the program could not run
+#[derive(Debug)]
+enum Failure {
+    /// The action, options or arguments could not be understood
+    Usage(String),
+    /// Some input could not be read
+    Unreadable(String),
+}
+
+/// Problems described only by a message are with the options or arguments
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Usage(message)
+    }
+}
+
+/// Options that may be given alongside the input paths
+#[derive(Debug, Default)]
+struct Options {
+    /// When to colorize diagnostics
+    color: ColorChoice,
+    /// How to output diagnostics
+    format: OutputFormat,
+    /// Report files that would be changed instead of changing them
+    check: bool,
+    /// Show the changes that would be made instead of making them
+    diff: bool,
+    /// Show the changes that would be made instead of making them
+    dry_run: bool,
+    /// Check that the files given are volumes carrying on from each other
+    volumes: bool,
+    /// Effective date of the first entry to renumber, as written in the
+    /// dialect
+    date: Option<String>,
+    /// New entry number of the first entry to renumber
+    number: Option<u32>,
+    /// Patterns for the names of files to read when recursing into directories
+    include: Vec<Pattern>,
+    /// Patterns for the names of files to skip when recursing into directories
+    exclude: Vec<Pattern>,
+    /// The words and formats the logbooks are written with
+    dialect: Dialect,
+    /// The lint rules to check the times of entries with
+    lints: Lints,
+    /// How to group entries when reporting statistics
+    grouping: Grouping,
+    /// The year to show a calendar of, instead of every year
+    year: Option<i32>,
+    /// The subject to show, along with the subjects within it
+    subject: Option<SubjectPath>,
+    /// Old subjects and the canonical subjects replacing them
+    aliases: Aliases,
+    /// Search for text regardless of case
+    ignore_case: bool,
+    /// Search for a regular expression instead of words
+    regex: bool,
+    /// Earliest effective date of the entries to search, as written in the
+    /// dialect
+    from: Option<String>,
+    /// Latest effective date of the entries to search, as written in the
+    /// dialect
+    to: Option<String>,
+}
+
+/// When to colorize output
+#[derive(Debug, Default, Clone, Copy)]
+enum ColorChoice {
+    /// Colorize if stdout is a terminal
+    #[default]
+    Auto,
+    /// Always colorize
+    Always,
+    /// Never colorize
+    Never,
+}
+
+/// How to output diagnostics
+#[derive(Debug, Default, Clone, Copy)]
+enum OutputFormat {
+    /// Rendered with source snippets, followed by a summary for each file
+    #[default]
+    Human,
+    /// A single JSON array of diagnostic objects
+    Json,
+    /// One JSON diagnostic object per line
+    JsonLines,
+}
+
+impl ColorChoice {
+    /// Decide whether output to stdout should be colorized
+    fn enabled(self) -> bool {
+        match self {
+            ColorChoice::Auto => std::io::stdout().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Separate options (flags given as `--name`, and values given as
+/// `--name=value` or `--name value`) from input paths
+fn parse_options(
+    args: &mut impl Iterator<Item = String>,
+) -> Result<(Options, Vec<PathBuf>), Failure> {
+    let mut options = Options::default();
+    let mut paths = Vec::new();
+    let mut next_day = None;
+    while let Some(arg) = args.next() {
+        let Some(option) = arg.strip_prefix("--") else {
+            paths.push(PathBuf::from(arg));
+            continue;
+        };
+        let (name, value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_owned())),
+            None => (option, None),
+        };
+        match name {
+            "check" => options.check = true,
+            "diff" => options.diff = true,
+            "dry-run" => options.dry_run = true,
+            "volumes" => options.volumes = true,
+            "ignore-case" => options.ignore_case = true,
+            "regex" => options.regex = true,
+            "color" => {
+                options.color = match option_value(name, value, args)?.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    other => {
+                        return Err(Failure::Usage(format!("Unrecognized color choice {other}")))
+                    }
+                }
+            }
+            "date" => options.date = Some(option_value(name, value, args)?),
+            "from" => options.from = Some(option_value(name, value, args)?),
+            "to" => options.to = Some(option_value(name, value, args)?),
+            "number" => {
+                let value = option_value(name, value, args)?;
+                options.number = Some(
+                    value
+                        .parse()
+                        .map_err(|error| format!("Bad entry number {value}: {error}"))?,
+                );
+            }
+            "include" => options
+                .include
+                .push(pattern(&option_value(name, value, args)?)?),
+            "exclude" => options
+                .exclude
+                .push(pattern(&option_value(name, value, args)?)?),
+            "dialect" => {
+                let file = PathBuf::from(option_value(name, value, args)?);
+                options.dialect =
+                    Dialect::load(&file).map_err(|error| Failure::Unreadable(error.to_string()))?;
+            }
+            "next-day" => next_day = Some(option_value(name, value, args)?),
+            "lint" => match option_value(name, value, args)?.as_str() {
+                "all" => Rule::ALL
+                    .into_iter()
+                    .for_each(|rule| options.lints.enable(rule)),
+                other => options.lints.enable(
+                    Rule::from_name(other).ok_or(format!("Unrecognized lint rule {other}"))?,
+                ),
+            },
+            "year" => {
+                let value = option_value(name, value, args)?;
+                options.year = Some(
+                    value
+                        .parse()
+                        .map_err(|error| format!("Bad year {value}: {error}"))?,
+                );
+            }
+            "subject" => {
+                options.subject = Some(SubjectPath::parse(&option_value(name, value, args)?))
+            }
+            "aliases" => {
+                let file = PathBuf::from(option_value(name, value, args)?);
+                let aliases =
+                    Aliases::load(&file).map_err(|error| Failure::Unreadable(error.to_string()))?;
+                for (old, canonical) in aliases.iter() {
+                    options
+                        .aliases
+                        .add(old.clone(), canonical.clone())
+                        .map_err(|error| format!("Bad alias in {}: {error}", file.display()))?;
+                }
+            }
+            "alias" => {
+                let value = option_value(name, value, args)?;
+                let (old, canonical) = value
+                    .split_once('=')
+                    .ok_or(format!("Bad alias {value}: expected <old>=<canonical>"))?;
+                options
+                    .aliases
+                    .add(SubjectPath::parse(old), SubjectPath::parse(canonical))
+                    .map_err(|error| format!("Bad alias {value}: {error}"))?;
+            }
+            "by" => {
+                options.grouping = match option_value(name, value, args)?.as_str() {
+                    "month" => Grouping::Month,
+                    "year" => Grouping::Year,
+                    other => return Err(Failure::Usage(format!("Unrecognized grouping {other}"))),
                 }
             }
-            Err(error) => Err(format!("Could not read path {}: {}", path.display(), error)),
-        })
-        .collect::<Result<Vec<_>, _>>()
-        .map(|paths| paths.into_iter().flatten().collect())
+            "max-duration" => {
+                let value = option_value(name, value, args)?;
+                let minutes = value
+                    .parse()
+                    .map_err(|error| format!("Bad maximum duration {value}: {error}"))?;
+                options.lints.set_max_duration(Duration::minutes(minutes));
+            }
+            "max-drift" => {
+                let value = option_value(name, value, args)?;
+                let days = value
+                    .parse()
+                    .map_err(|error| format!("Bad maximum drift {value}: {error}"))?;
+                options.lints.set_max_drift(Duration::days(days));
+            }
+            "format" => {
+                options.format = match option_value(name, value, args)?.as_str() {
+                    "human" => OutputFormat::Human,
+                    "json" => OutputFormat::Json,
+                    "jsonl" => OutputFormat::JsonLines,
+                    other => {
+                        return Err(Failure::Usage(format!(
+                            "Unrecognized output format {other}"
+                        )))
+                    }
+                }
+            }
+            _ => return Err(Failure::Usage(format!("Unrecognized option --{name}"))),
+        }
+    }
+    // The next-day hour overrides the dialect's, whichever was given first
+    if let Some(hour) = next_day {
+        options.dialect.set("next_day", &hour)?;
+    }
+    Ok((options, paths))
+}
+
+/// Compile a file name pattern given as an option
+fn pattern(pattern: &str) -> Result<Pattern, String> {
+    Pattern::new(pattern).map_err(|error| format!("Bad pattern {pattern}: {error}"))
+}
+
+/// Get the value of an option, given either inline or as the next argument
+fn option_value(
+    name: &str,
+    inline: Option<String>,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, String> {
+    inline
+        .or_else(|| args.next())
+        .ok_or(format!("No value given for option --{name}"))
+}
+
+/// Expand a series of paths by recursing into directories, in order of name.
+/// Files given directly are always kept, while files found in directories must
+/// have a name matching an include pattern (by default, any `.txt` file) and no
+/// exclude pattern. Hidden directories are skipped, and each file or directory
+/// is only visited once however it is reached, so symlink loops end and no
+/// file is read twice
+fn expand_paths(paths: Vec<PathBuf>, options: &Options) -> Result<Vec<PathBuf>, String> {
+    let default_include = [Pattern::new(DEFAULT_INCLUDE).expect("default pattern is valid")];
+    let mut expansion = Expansion {
+        include: if options.include.is_empty() {
+            &default_include
+        } else {
+            &options.include
+        },
+        exclude: &options.exclude,
+        visited: HashSet::new(),
+        files: Vec::new(),
+    };
+    for path in paths {
+        expansion.visit(&path, true)?;
+    }
+    Ok(expansion.files)
+}
+
+/// The state of a path expansion
+struct Expansion<'a> {
+    /// Patterns that names of files found in directories must match one of
+    include: &'a [Pattern],
+    /// Patterns that names of files found in directories must not match
+    exclude: &'a [Pattern],
+    /// Canonical forms of every path visited so far
+    visited: HashSet<PathBuf>,
+    /// Files found so far
+    files: Vec<PathBuf>,
+}
+
+impl Expansion<'_> {
+    /// Visit a path, which was either given directly or found in a directory
+    fn visit(&mut self, path: &Path, given: bool) -> Result<(), String> {
+        let error = |error: io::Error| format!("Could not read path {}: {}", path.display(), error);
+        let metadata = fs::metadata(path).map_err(error)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        // Skip hidden directories and files that don't match the patterns,
+        // unless they were given directly
+        if !given {
+            if metadata.is_dir() && name.starts_with('.') {
+                return Ok(());
+            }
+            if !metadata.is_dir()
+                && (!self.include.iter().any(|pattern| pattern.matches(&name))
+                    || self.exclude.iter().any(|pattern| pattern.matches(&name)))
+            {
+                return Ok(());
+            }
+        }
+        // Skip anything already visited
+        if !self.visited.insert(fs::canonicalize(path).map_err(error)?) {
+            return Ok(());
+        }
+        if metadata.is_dir() {
+            let mut entries = fs::read_dir(path)
+                .map_err(error)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(error)?;
+            entries.sort();
+            for entry in entries {
+                self.visit(&entry, false)?;
+            }
+        } else {
+            self.files.push(path.to_path_buf());
+        }
+        Ok(())
+    }
 }
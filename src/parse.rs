@@ -1,109 +1,299 @@
-use crate::{Block, Entry, Logbook, Mark};
-use std::path::PathBuf;
-use time::macros::{format_description, time};
+use crate::diagnostic::{Code, Diagnostic, Span};
+use crate::dialect::{DateStyle, Dialect, Style, TimeStyle};
+use crate::syntax::{
+    EntryNode, LineNode, PageHeaderNode, PageNode, PreambleNode, SyntaxTree, Token,
+};
+use crate::zone::{self, Zone};
+use crate::{Block, Entry, Logbook, Mark, Page, PageEntry, PageHeader};
+use std::path::{Path, PathBuf};
+use time::macros::time;
 use time::{Date, Duration, PrimitiveDateTime, Time};
 
-/// Parse a series of files
-pub fn parse_files(files: Vec<PathBuf>) -> Result<Vec<Logbook>, String> {
-    files
-        .into_iter()
-        .map(|file| {
-            std::fs::read_to_string(&file)
-                .map_err(|error| format!("Could not read file {}: {}", file.display(), error))
-                .map(|text| (file, text))
-        })
-        .collect::<Result<Vec<_>, _>>()?
-        .into_iter()
-        .map(|(file, text)| {
-            parse(&text)
-                .map_err(|error| format!("{}\nwhile parsing file {}", error, file.display()))
-        })
-        .collect()
+/// The result of successfully parsing a logbook
+#[derive(Debug)]
+pub struct ParseOutcome {
+    /// The parsed logbook
+    logbook: Logbook,
+    /// Problems that were recovered from while parsing
+    diagnostics: Vec<Diagnostic>,
 }
 
-/// Parse a logbook
-pub fn parse(logbook: &str) -> Result<Logbook, String> {
-    // Extract the first page of the logbook
-    let (preamble, entries) = logbook
-        .split_once(&(PAGE_MARKER.to_owned() + COMPONENT_SEPARATOR))
-        .ok_or("Could not parse preamble - no page boundry markers")?;
+impl ParseOutcome {
+    pub fn new(logbook: Logbook, diagnostics: Vec<Diagnostic>) -> Self {
+        Self {
+            logbook,
+            diagnostics,
+        }
+    }
+    pub fn logbook(&self) -> &Logbook {
+        &self.logbook
+    }
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+    /// Attach the file the logbook was read from to all diagnostics
+    pub fn with_file(mut self, file: &Path) -> Self {
+        self.diagnostics = self
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| diagnostic.with_file(file))
+            .collect();
+        self
+    }
+    /// Split this outcome into the logbook and its diagnostics
+    pub fn into_parts(self) -> (Logbook, Vec<Diagnostic>) {
+        (self.logbook, self.diagnostics)
+    }
+}
+
+/// Parse a series of files written in a dialect
+pub fn parse_files(
+    files: Vec<PathBuf>,
+    dialect: &Dialect,
+) -> Result<Vec<ParseOutcome>, Diagnostic> {
+    files.iter().map(|file| parse_file(file, dialect)).collect()
+}
+
+/// Parse a single file written in a dialect, attaching the file to any
+/// diagnostics
+pub fn parse_file(file: &Path, dialect: &Dialect) -> Result<ParseOutcome, Diagnostic> {
+    let text = read_file(file)?;
+    parse_with_dialect(&text, dialect)
+        .map(|outcome| outcome.with_file(file))
+        .map_err(|error| error.with_file(file))
+}
+
+/// Read the text of a file, reporting failure as a diagnostic
+pub fn read_file(file: &Path) -> Result<String, Diagnostic> {
+    std::fs::read_to_string(file).map_err(|error| {
+        Diagnostic::error(
+            Code::UnreadableFile,
+            format!("Could not read file: {error}"),
+        )
+        .with_file(file)
+    })
+}
+
+/// Parse a logbook. Unrecoverable problems are returned as an error, while
+/// anything the parser could recover from is returned alongside the logbook
+pub fn parse(logbook: &str) -> Result<ParseOutcome, Diagnostic> {
+    parse_tree(&SyntaxTree::new(logbook))
+}
+
+/// Parse a logbook written in a dialect, in the same way as [`parse`]
+pub fn parse_with_dialect(logbook: &str, dialect: &Dialect) -> Result<ParseOutcome, Diagnostic> {
+    parse_tree(&SyntaxTree::with_dialect(logbook, dialect))
+}
+
+/// Derive a logbook from its syntax tree, in the same way as [`parse`]
+pub fn parse_tree(tree: &SyntaxTree) -> Result<ParseOutcome, Diagnostic> {
+    let source = tree.source();
+    let dialect = tree.dialect();
+    // The preamble must be followed by at least one page
+    if tree.pages().is_empty() {
+        return Err(Diagnostic::error(
+            Code::MissingPageBreak,
+            "Could not parse preamble - no page boundry markers",
+        )
+        .with_span(Span::new(source.len(), source.len()))
+        .locate(source));
+    }
     // Parse the preamble
-    let (start, end) =
-        parse_preamble(preamble).map_err(|error| format!("Could not parse preamble - {error}"))?;
-    // Parse the entries
-    let (entries, errors) = entries
-        .split(COMPONENT_SEPARATOR)
-        .fold(EntryParser::new(start.clone()), |parser, chunk| {
-            parser.advance(chunk)
-        })
-        .finish(end.clone());
-    // Print parsing errors
-    for error in errors {
-        eprintln!("Parsing error: {error}\n");
+    let mut styles = Styles::default();
+    let mut preamble_errors = Vec::new();
+    let (start, end, end_span) = parse_preamble(
+        source,
+        tree.preamble(),
+        dialect,
+        &mut styles,
+        &mut preamble_errors,
+    )
+    .map_err(|error| error.with_note("while parsing the preamble").locate(source))?;
+    let next_day = parse_day_start(
+        source,
+        tree.preamble(),
+        dialect,
+        &mut styles,
+        &mut preamble_errors,
+    );
+    // Parse the pages
+    let mut parser = EntryParser::new(source, dialect, next_day, start.clone(), styles);
+    for page in tree.pages() {
+        parser.page(page);
     }
-    Ok(Logbook::new(start, end, entries))
+    let (entries, pages, style, errors) = parser.finish(end.clone(), end_span);
+    // Resolve error locations against the source text
+    let errors = preamble_errors
+        .into_iter()
+        .chain(errors)
+        .map(|error| error.locate(source))
+        .collect();
+    let mut logbook = Logbook::new(start, end, entries);
+    logbook.set_pages(pages);
+    logbook.set_style(style);
+    logbook.set_next_day(next_day);
+    Ok(ParseOutcome::new(logbook, errors))
 }
 
-/// Parse the preable of a logbook, extracting the start and end marks
-fn parse_preamble(preable: &str) -> Result<(Mark, Option<Mark>), String> {
+/// Parse the preable of a logbook, extracting the start and end marks and the
+/// span of the end mark. The styles of the dates are noted, and any mixing of
+/// them is recorded as a warning
+fn parse_preamble(
+    source: &str,
+    preamble: &PreambleNode,
+    dialect: &Dialect,
+    styles: &mut Styles,
+    warnings: &mut Vec<Diagnostic>,
+) -> Result<(Mark, Option<Mark>, Span), Diagnostic> {
     // Extract components
-    let mut words = preable
-        .split_once(ENTRY_RANGE_START)
-        .ok_or("no entry range found")?
-        .1
-        .split_ascii_whitespace();
-    let start_date = expect_value(words.next(), "start date", "entry range")?;
+    let range = preamble.entry_range().ok_or_else(|| {
+        Diagnostic::error(Code::MissingEntryRange, "no entry range found")
+            .with_span(preamble.span())
+    })?;
+    let mut words = range.words().iter().map(|word| Token::of(source, *word));
+    let range = Token::of(source, range.span());
+    let start_date = expect_value(words.next(), "start date", "entry range", range)?;
     expect_literal(
         words.next(),
-        ENTRY_RANGE_MARK_SEPARATOR,
+        dialect.entry_range_mark_separator(),
         "entry range start separator",
+        range,
+    )?;
+    let start_number = expect_value(words.next(), "start entry number", "entry range", range)?;
+    expect_literal(
+        words.next(),
+        dialect.entry_range_separator(),
+        "entry range separator",
+        range,
     )?;
-    let start_number = expect_value(words.next(), "start entry number", "entry range")?;
-    expect_literal(words.next(), ENTRY_RANGE_SEPARATOR, "entry range separator")?;
-    let end_date = expect_value(words.next(), "end date", "entry range")?;
+    let end_date = expect_value(words.next(), "end date", "entry range", range)?;
     expect_literal(
         words.next(),
-        ENTRY_RANGE_MARK_SEPARATOR,
+        dialect.entry_range_mark_separator(),
         "entry range end separator",
+        range,
     )?;
-    let end_number = expect_value(words.next(), "end entry number", "entry range")?;
+    let end_number = expect_value(words.next(), "end entry number", "entry range", range)?;
     // Parse and structure marks
+    let mut date = |token: Token, name| {
+        let (date, style) = parse_date(token, name, dialect)?;
+        warnings.extend(styles.date(style, token.span(), dialect));
+        Ok::<_, Diagnostic>(date)
+    };
     let start = Mark::new(
-        parse_date(start_date, "start date")?,
+        date(start_date, "start date")?,
         parse_number(start_number, "start entry number")?,
     );
-    let end = if end_number == ENTRY_RANGE_PLACEHOLDER {
+    let end = if end_number.text == dialect.entry_range_placeholder() {
         None
     } else {
         Some(Mark::new(
-            parse_date(end_date, "end date")?,
+            date(end_date, "end date")?,
             parse_number(end_number, "end entry number")?,
         ))
     };
-    Ok((start, end))
+    Ok((start, end, end_date.span().to(end_number.span())))
+}
+
+/// Parse the declaration of when days start in the preamble of a logbook,
+/// returning the hour before which entries count for the previous day. Without
+/// a declaration, or if it is malformed, the dialect's hour is used and any
+/// problems are recorded
+fn parse_day_start(
+    source: &str,
+    preamble: &PreambleNode,
+    dialect: &Dialect,
+    styles: &mut Styles,
+    errors: &mut Vec<Diagnostic>,
+) -> u8 {
+    let Some(day_start) = preamble.day_start() else {
+        return dialect.next_day();
+    };
+    let within = Token::of(source, day_start.span());
+    let mut words = day_start
+        .words()
+        .iter()
+        .map(|word| Token::of(source, *word));
+    let result = read_time(
+        &mut words,
+        "day start time",
+        "day start declaration",
+        within,
+        dialect,
+    )
+    .and_then(|(time, style, span)| {
+        errors.extend(styles.time(style, span, dialect));
+        if time.minute() != 0 {
+            return Err(Diagnostic::error(
+                Code::BadTime,
+                "bad day start time - days must start on the hour",
+            )
+            .with_span(span));
+        }
+        Ok(time.hour())
+    });
+    if let (Ok(_), Some(extra)) = (&result, words.next()) {
+        errors.push(
+            Diagnostic::warning(
+                Code::TrailingText,
+                format!(
+                    "Unexpected text after day start declaration: {}",
+                    extra.text
+                ),
+            )
+            .with_span(extra.span()),
+        );
+    }
+    result.unwrap_or_else(|error| {
+        errors.push(error.with_note("while parsing the preamble"));
+        dialect.next_day()
+    })
 }
 
 /// A state-based parser for entries (and page headers)
 #[derive(Debug)]
-struct EntryParser {
+struct EntryParser<'a> {
+    /// The source text being parsed
+    source: &'a str,
+    /// The dialect the source text is written in
+    dialect: &'a Dialect,
+    /// The hour before which entries count for the previous day
+    next_day: u8,
     /// The next expected entry position
     next_entry_position: Mark,
     // The entry currently being read
     current_entry: Entry,
+    /// The span of the current entry's header, if one has been read
+    current_entry_header: Option<Span>,
     /// State flag for multi-page entries
     multi_page_flag: bool,
     /// Expectations imposed by the most recent page header
     page_header_expectations: PageHeaderExpectations,
+    /// The time zone given in the current page's header, if any
+    page_zone: Option<Zone>,
     /// Previously-read entries
     read_entries: Vec<Entry>,
+    /// Pages read so far, the last being the current page
+    pages: Vec<Page>,
+    /// The styles dates and times have been written in so far
+    styles: Styles,
     /// Errors encountered during parsing
-    errors: Vec<String>,
+    errors: Vec<Diagnostic>,
 }
 
-impl EntryParser {
+impl<'a> EntryParser<'a> {
     /// Create a new parser
-    pub fn new(expected_start: Mark) -> Self {
+    pub fn new(
+        source: &'a str,
+        dialect: &'a Dialect,
+        next_day: u8,
+        expected_start: Mark,
+        styles: Styles,
+    ) -> Self {
         Self {
+            source,
+            dialect,
+            next_day,
             next_entry_position: expected_start,
             current_entry: Entry::new(
                 Mark::new(Date::MIN, 0),
@@ -111,323 +301,543 @@ impl EntryParser {
                 PrimitiveDateTime::MIN,
                 Vec::new(),
             ),
+            current_entry_header: None,
             multi_page_flag: false,
             page_header_expectations: PageHeaderExpectations::NewHeader,
+            page_zone: None,
             read_entries: Vec::new(),
+            pages: Vec::new(),
+            styles,
             errors: Vec::new(),
         }
     }
 
-    /// Advance the parser over a chunk of input
-    pub fn advance(mut self, chunk: &str) -> Self {
-        let result = self.try_advance(chunk);
+    /// Advance the parser over a page
+    pub fn page(&mut self, page: &PageNode) {
+        // The first page marker ends the preamble rather than a page
+        if !self.pages.is_empty() {
+            self.page_break(page.marker());
+        }
+        self.pages
+            .push(Page::new(self.pages.len(), None, Vec::new()));
+        self.page_zone = None;
+        if let Some(header) = page.header() {
+            self.page_header(header);
+        }
+        for entry in page.entries() {
+            self.entry(entry);
+        }
+    }
+
+    /// Advance the parser over a page marker, ending the current page
+    fn page_break(&mut self, marker: Span) {
+        let result = self.try_page_break(marker);
+        self.record(result);
+        // A page header always follows a page marker
+        self.page_header_expectations = PageHeaderExpectations::NewHeader;
+    }
+
+    /// Advance the parser over a page header
+    fn page_header(&mut self, header: &PageHeaderNode) {
+        if let Some(zone) = header.zone() {
+            let result = self.page_zone(zone);
+            self.record(result);
+        }
+        let result = self.try_page_header(header);
         self.record(result);
-        self
     }
 
-    /// Extract the parsed entries and any encountered errors
-    pub fn finish(mut self, end: Option<Mark>) -> (Vec<Entry>, Vec<String>) {
+    /// Advance the parser over an entry or part of an entry
+    fn entry(&mut self, entry: &EntryNode) {
+        let result = self.try_entry(entry);
+        self.record(result);
+    }
+
+    /// Extract the parsed entries and pages, the styles they were written in
+    /// and any encountered errors
+    pub fn finish(
+        mut self,
+        end: Option<Mark>,
+        end_span: Span,
+    ) -> (Vec<Entry>, Vec<Page>, Style, Vec<Diagnostic>) {
         if let Some(end) = end {
             if let Some(last) = self.read_entries.last() {
                 if *last.position() != end {
-                    self.error(format!(
+                    let error = Diagnostic::error(Code::EndMismatch, format!(
                         "logbook does not end with specified end date and/or entry number, expected {} {} but got {} {}",
                         end.effective_date(),
                         end.entry_number(),
                         last.position.effective_date(),
                         last.position.entry_number(),
-                    ));
+                    )).with_span(end_span);
+                    let error = label(error, self.current_entry_header, "last entry");
+                    self.error(error);
                 }
             } else {
                 self.error(
-                    "an end entry was specified but the logbook contains no entries".to_string(),
+                    Diagnostic::error(
+                        Code::EndMismatch,
+                        "an end entry was specified but the logbook contains no entries",
+                    )
+                    .with_span(end_span),
                 );
             }
         }
-        (self.read_entries, self.errors)
-    }
-
-    /// Attempt to advance the parser
-    fn try_advance(&mut self, chunk: &str) -> Result<(), String> {
-        if matches!(
-            self.page_header_expectations,
-            PageHeaderExpectations::NewHeader
-        ) {
-            // The previous page just ended, read in the new header
-            // We haven't looked at the new header yet, just assume
-            // it's broken for now
-            self.page_header_expectations = PageHeaderExpectations::Error;
-            // Extract components
-            let (number_range, date_range) = chunk
-                .split_once(PAGE_RANGE_SPLIT)
-                .ok_or("no separator between number and date ranges of page header")?;
-            let (number_start, number_end) = number_range
-                .split_once(PAGE_RANGE_SEPARATOR)
-                .ok_or("no separator between start and end of entry number range of page header")?;
-            let (date_start, date_end) = date_range
-                .split_once(PAGE_RANGE_SEPARATOR)
-                .ok_or("no separator between start and end of date range of page header")?;
-            // Parse and structure values
-            self.page_header_expectations = PageHeaderExpectations::StartAndEnd {
-                start_recorded_date: parse_date(date_start, "page header start date")?,
-                start_number: parse_number(number_start, "page header start number")?,
-                end_recorded_date: parse_date(date_end, "page header end date")?,
-                end_number: parse_number(number_end, "page header end number")?,
-            }
-        } else if chunk == PAGE_MARKER {
-            // The current page is ending, check the page header's end against
-            // the previous entry
-            match self.page_header_expectations {
-                PageHeaderExpectations::NewHeader => Err("expected page header, got page break"),
-                PageHeaderExpectations::StartAndEnd { .. } => {
-                    Err("expected at least one entry after page header, got page break")
-                }
-                PageHeaderExpectations::End {
-                    end_recorded_date,
-                    end_number,
-                } => {
-                    // Check the ending entry
-                    if self.current_entry.recorded_date() != end_recorded_date {
-                        self.error(format!(
+        (
+            self.read_entries,
+            self.pages,
+            self.styles.style(),
+            self.errors,
+        )
+    }
+
+    /// Attempt to end the current page
+    fn try_page_break(&mut self, marker: Span) -> Result<(), Diagnostic> {
+        // Check the page header's end against the previous entry
+        match self.page_header_expectations {
+            PageHeaderExpectations::NewHeader => Err(Diagnostic::error(
+                Code::UnexpectedPageBreak,
+                "expected page header, got page break",
+            )),
+            PageHeaderExpectations::StartAndEnd { .. } => Err(Diagnostic::error(
+                Code::EmptyPage,
+                "expected at least one entry after page header, got page break",
+            )),
+            PageHeaderExpectations::End {
+                end_recorded_date,
+                end_number,
+            } => {
+                // Check the ending entry
+                if self.current_entry.recorded_date() != end_recorded_date.value {
+                    let error = Diagnostic::error(
+                        Code::PageHeaderMismatch,
+                        format!(
                             "page header end date mismatch: header ends on {}, last entry is {}",
-                            end_recorded_date,
+                            end_recorded_date.value,
                             self.current_entry.recorded_date(),
-                        ));
-                    }
-                    if self.current_entry.position().entry_number() != end_number {
-                        self.error(format!(
+                        ),
+                    )
+                    .with_span(end_recorded_date.span);
+                    let error = label(error, self.current_entry_header, "last entry on this page");
+                    self.error(error);
+                }
+                if self.current_entry.position().entry_number() != end_number.value {
+                    let error = Diagnostic::error(
+                        Code::PageHeaderMismatch,
+                        format!(
                             "page header end number mismatch: header ends on {}, last entry is {}",
-                            end_number,
+                            end_number.value,
                             self.current_entry.position().entry_number(),
-                        ));
-                    }
-                    // The next chunk should be the new page header
-                    self.page_header_expectations = PageHeaderExpectations::NewHeader;
-                    Ok(())
+                        ),
+                    )
+                    .with_span(end_number.span);
+                    let error = label(error, self.current_entry_header, "last entry on this page");
+                    self.error(error);
                 }
-                PageHeaderExpectations::Error => Ok(()),
-            }?;
-        } else {
-            // This is some part of an entry
-            // If this is the first entry of the page, get the expected entry
-            // number and date from the page header
-            let page_header_expectations = match self.page_header_expectations {
-                PageHeaderExpectations::NewHeader => Err("expected page header, got entry"),
-                PageHeaderExpectations::StartAndEnd {
-                    start_recorded_date,
-                    start_number,
+                Ok(())
+            }
+            PageHeaderExpectations::Error => Ok(()),
+        }
+        .map_err(|error| error.with_span(marker))
+    }
+
+    /// Attempt to read a page header
+    fn try_page_header(&mut self, header: &PageHeaderNode) -> Result<(), Diagnostic> {
+        // We haven't looked at the new header yet, just assume
+        // it's broken for now
+        self.page_header_expectations = PageHeaderExpectations::Error;
+        // Extract components
+        let malformed = |message: &str| {
+            Diagnostic::error(Code::MalformedPageHeader, message).with_span(header.span())
+        };
+        let numbers = header.numbers();
+        let dates = header.dates().ok_or_else(|| {
+            malformed("no separator between number and date ranges of page header")
+        })?;
+        let number_end = numbers.end().ok_or_else(|| {
+            malformed("no separator between start and end of entry number range of page header")
+        })?;
+        let date_end = dates.end().ok_or_else(|| {
+            malformed("no separator between start and end of date range of page header")
+        })?;
+        let number_start = self.token(numbers.start());
+        let number_end = self.token(number_end);
+        let date_start = self.token(dates.start());
+        let date_end = self.token(date_end);
+        // Parse and structure values
+        let start_recorded_date = self.date(date_start, "page header start date")?;
+        let start_number = parse_number(number_start, "page header start number")?;
+        let end_recorded_date = self.date(date_end, "page header end date")?;
+        let end_number = parse_number(number_end, "page header end number")?;
+        if let Some(page) = self.pages.last_mut() {
+            let mut page_header = PageHeader::new(
+                start_number,
+                end_number,
+                start_recorded_date,
+                end_recorded_date,
+            );
+            page_header.set_zone(self.page_zone);
+            page.set_header(Some(page_header));
+        }
+        self.page_header_expectations = PageHeaderExpectations::StartAndEnd {
+            start_recorded_date: Spanned::new(start_recorded_date, date_start.span()),
+            start_number: Spanned::new(start_number, number_start.span()),
+            end_recorded_date: Spanned::new(end_recorded_date, date_end.span()),
+            end_number: Spanned::new(end_number, number_end.span()),
+        };
+        Ok(())
+    }
+
+    /// Attempt to read an entry or part of an entry
+    fn try_entry(&mut self, entry: &EntryNode) -> Result<(), Diagnostic> {
+        let continued = self.multi_page_flag;
+        // If this is the first entry of the page, get the expected entry
+        // number and date from the page header
+        let page_header_expectations = match self.page_header_expectations {
+            PageHeaderExpectations::NewHeader => Err(Diagnostic::error(
+                Code::MissingPageHeader,
+                "expected page header, got entry",
+            )
+            .with_span(entry.span())),
+            PageHeaderExpectations::StartAndEnd {
+                start_recorded_date,
+                start_number,
+                end_recorded_date,
+                end_number,
+            } => {
+                // We're checking the start entry, check the end entry next
+                self.page_header_expectations = PageHeaderExpectations::End {
                     end_recorded_date,
                     end_number,
-                } => {
-                    // We're checking the start entry, check the end entry next
-                    self.page_header_expectations = PageHeaderExpectations::End {
-                        end_recorded_date,
-                        end_number,
-                    };
-                    Ok(Some((start_recorded_date, start_number)))
-                }
-                PageHeaderExpectations::End { .. } | PageHeaderExpectations::Error => Ok(None),
-            }?;
-            // Split the entry into lines
-            let mut lines = chunk.lines();
-            // Don't look for a header if we are continuing a mult-page entry
-            if !self.multi_page_flag {
-                // Extrapolate the next expected entry mark
-                let expected_here = self.next_entry_position.clone();
-                self.next_entry_position = Mark::new(
-                    expected_here.effective_date() + Duration::DAY,
-                    expected_here.entry_number() + 1,
+                };
+                Ok(Some((start_recorded_date, start_number)))
+            }
+            PageHeaderExpectations::End { .. } | PageHeaderExpectations::Error => Ok(None),
+        }?;
+        // Don't look for a header if we are continuing a mult-page entry
+        if !self.multi_page_flag {
+            // Extrapolate the next expected entry mark
+            let expected_here = self.next_entry_position.clone();
+            self.next_entry_position = Mark::new(
+                expected_here.effective_date() + Duration::DAY,
+                expected_here.entry_number() + 1,
+            );
+            // Be generous and assume that this entry had the right position
+            // when checking page headers if parsing fails
+            self.current_entry.set_position(expected_here.clone());
+            self.current_entry.set_started(PrimitiveDateTime::new(
+                expected_here.effective_date(),
+                time!(9 pm),
+            ));
+            // Get the header
+            let header_node = entry.header().ok_or_else(|| {
+                Diagnostic::error(Code::MalformedEntryHeader, "entry has no header")
+                    .with_span(entry.span())
+            })?;
+            let header_line = self.token(header_node.span());
+            let previous_header = self.current_entry_header.replace(header_line.span());
+            let mut header = header_node
+                .words()
+                .iter()
+                .map(|word| self.token(*word))
+                .collect::<Vec<_>>()
+                .into_iter();
+            // Extract components
+            let dialect = self.dialect;
+            self.record(expect_literal(
+                header.next(),
+                dialect.header_start(),
+                "entry header start",
+                header_line,
+            ));
+            let number = expect_value(header.next(), "entry number", "entry header", header_line)?;
+            let number = number
+                .strip_suffix(dialect.header_post_number())
+                .ok_or_else(|| {
+                    Diagnostic::error(
+                        Code::MalformedEntryHeader,
+                        "missing separator between entry number and date in entry header",
+                    )
+                    .with_span(number.end())
+                })?;
+            let start_date =
+                expect_value(header.next(), "entry date", "entry header", header_line)?;
+            self.record(expect_literal(
+                header.next(),
+                dialect.header_started(),
+                "entry header started",
+                header_line,
+            ));
+            let (start_time, start_style, start_span) = read_time(
+                &mut header,
+                "entry started time",
+                "entry header",
+                header_line,
+                dialect,
+            )?;
+            let start_zone = read_zone(&mut header, "entry started time zone");
+            let start_zone = self.record(start_zone).flatten();
+            self.record(expect_literal(
+                header.next(),
+                dialect.header_finished(),
+                "entry header finished",
+                header_line,
+            ));
+            let (end_time, end_style, end_span) = read_time(
+                &mut header,
+                "entry finished time",
+                "entry header",
+                header_line,
+                dialect,
+            )?;
+            let end_zone = read_zone(&mut header, "entry finished time zone");
+            let end_zone = self.record(end_zone).flatten();
+            if let Some(extra) = header.next() {
+                self.error(
+                    Diagnostic::warning(
+                        Code::TrailingText,
+                        format!("Unexpected text after entry header: {}", extra.text),
+                    )
+                    .with_span(extra.span()),
                 );
-                // Be generous and assume that this entry had the right position
-                // when checking page headers if parsing fails
-                self.current_entry.set_position(expected_here.clone());
-                self.current_entry.set_started(PrimitiveDateTime::new(
-                    expected_here.effective_date(),
-                    time!(9 pm),
-                ));
-                // Get the header
-                let mut header = lines.next().ok_or("entry has no header")?.split(" ");
-                // Extract components
-                self.record(expect_literal(
-                    header.next(),
-                    HEADER_START,
-                    "entry header start",
-                ));
-                let number = expect_value(header.next(), "entry number", "entry header")?;
-                let number = number
-                    .strip_suffix(HEADER_POST_NUMBER)
-                    .ok_or("missing separator between entry number and date in entry header")?;
-                let start_date = expect_value(header.next(), "entry date", "entry header")?;
-                self.record(expect_literal(
-                    header.next(),
-                    HEADER_STARTED,
-                    "entry header started",
-                ));
-                let start_time = expect_value(header.next(), "entry started time", "entry header")?;
-                let start_period =
-                    expect_value(header.next(), "entry started period", "entry header")?;
-                self.record(expect_literal(
-                    header.next(),
-                    HEADER_FINISHED,
-                    "entry header finished",
-                ));
-                let end_time = expect_value(header.next(), "entry finished time", "entry header")?;
-                let end_period =
-                    expect_value(header.next(), "entry finished period", "entry header")?;
-                if let Some(extra) = header.next() {
-                    self.error(format!("Unexpected text after entry header: {extra}"));
+            }
+            // Parse and structure values
+            let entry_number = parse_number(number, "entry number")?;
+            let start_date_value = self.date(start_date, "entry date")?;
+            for (style, span) in [(start_style, start_span), (end_style, end_span)] {
+                if let Some(warning) = self.styles.time(style, span, dialect) {
+                    self.error(warning);
                 }
-                // Parse and structure values
-                let entry_number = parse_number(number, "entry number")?;
-                let start_date = parse_date(start_date, "entry date")?;
-                let start_time = parse_time(
-                    &format!("{} {}", start_time, start_period),
-                    "entry start time",
-                )?;
-                let end_time =
-                    parse_time(&format!("{} {}", end_time, end_period), "entry end time")?;
-                // Calculate end timestamp and effective entry date
-                let start = PrimitiveDateTime::new(start_date, start_time);
-                let end_date = if start_time < end_time {
-                    // This entry was finished the same day it was started
-                    start_date
-                } else {
-                    // This entry was finished the day after it was started
-                    start_date + Duration::DAY
-                };
-                let end = PrimitiveDateTime::new(end_date, end_time);
-                let date = if start.hour() > NEXT_DAY {
-                    // Past the previous-day threshold, the effective and recorded
-                    // dates are the same
-                    start_date
+            }
+            // Times without a zone of their own are in the page's zone, or
+            // the zone the entry was started in
+            let start_zone = start_zone.or(self.page_zone);
+            let end_zone = end_zone.or(start_zone);
+            // Calculate end timestamp and effective entry date
+            let start = PrimitiveDateTime::new(start_date_value, start_time);
+            let same_day_end = PrimitiveDateTime::new(start_date_value, end_time);
+            let end_date = if zone::assume(start, start_zone) < zone::assume(same_day_end, end_zone)
+            {
+                // This entry was finished the same day it was started
+                start_date_value
+            } else {
+                // This entry was finished the day after it was started
+                start_date_value + Duration::DAY
+            };
+            let end = PrimitiveDateTime::new(end_date, end_time);
+            // Before the previous-day threshold, this is a past-midnight
+            // entry and has an effective date of the previous day
+            let date = crate::effective_date(start, self.next_day);
+            // Calculate entry position
+            let position = Mark::new(date, entry_number);
+            // Calculate the next expected entry position
+            let expected_next = Mark::new(date + Duration::DAY, entry_number + 1);
+            // Check that this entry has the expected position
+            if expected_here.effective_date() != position.effective_date() {
+                let error = if expected_here.effective_date() + Duration::DAY
+                    == position.effective_date()
+                {
+                    Diagnostic::error(
+                        Code::MissingEntry,
+                        format!("Missing entry for {}", expected_here.effective_date()),
+                    )
                 } else {
-                    // Before the previous-day threshold, this is a past-midnight
-                    // entry and has an effective date of the previous day
-                    start_date - Duration::DAY
-                };
-                // Calculate entry position
-                let position = Mark::new(date, entry_number);
-                // Calculate the next expected entry position
-                let expected_next = Mark::new(date + Duration::DAY, entry_number + 1);
-                // Check that this entry has the expected position
-                if expected_here.effective_date() != position.effective_date() {
-                    if expected_here.effective_date() + Duration::DAY == position.effective_date() {
-                        self.error(format!(
-                            "Missing entry for {}",
-                            expected_here.effective_date(),
-                        ));
-                    } else {
-                        self.error(format!(
+                    Diagnostic::error(
+                        Code::DateMismatch,
+                        format!(
                             "Expected {} for effective date, got {}",
                             expected_here.effective_date(),
                             position.effective_date(),
-                        ));
-                    }
+                        ),
+                    )
                 }
-                if expected_here.entry_number() != position.entry_number() {
-                    self.error(format!(
+                .with_span(start_date.span());
+                self.error(label(error, previous_header, "previous entry"));
+            }
+            if expected_here.entry_number() != position.entry_number() {
+                let error = Diagnostic::error(
+                    Code::NumberMismatch,
+                    format!(
                         "Expected {} for entry number, got {}",
                         expected_here.entry_number(),
                         position.entry_number(),
-                    ));
-                }
-                // Update the next expected entry position. This should be the
-                // same as the extrapolated version, but may be different in the
-                // case of a missed entry
-                self.next_entry_position = expected_next;
-                // Start recording the new entry
-                self.current_entry = Entry::new(position, start, end, Vec::new());
+                    ),
+                )
+                .with_span(number.span());
+                self.error(label(error, previous_header, "previous entry"));
             }
-            // Check the header constraints if present
-            if let Some((date, number)) = page_header_expectations {
-                if date != self.current_entry.recorded_date() {
-                    self.error(format!(
+            // Update the next expected entry position. This should be the
+            // same as the extrapolated version, but may be different in the
+            // case of a missed entry
+            self.next_entry_position = expected_next;
+            // Start recording the new entry
+            self.current_entry = Entry::new(position, start, end, Vec::new());
+            self.current_entry.set_started_zone(start_zone);
+            self.current_entry.set_finished_zone(end_zone);
+        }
+        // Check the header constraints if present
+        if let Some((date, number)) = page_header_expectations {
+            if date.value != self.current_entry.recorded_date() {
+                let error = Diagnostic::error(
+                    Code::PageHeaderMismatch,
+                    format!(
                         "page header start date mismatch: header starts on {}, first entry is {}",
-                        date,
+                        date.value,
                         self.current_entry.recorded_date()
-                    ));
-                }
-                if number != self.current_entry.position().entry_number() {
-                    self.error(format!(
+                    ),
+                )
+                .with_span(date.span);
+                self.error(label(
+                    error,
+                    self.current_entry_header,
+                    "first entry on this page",
+                ));
+            }
+            if number.value != self.current_entry.position().entry_number() {
+                let error = Diagnostic::error(
+                    Code::PageHeaderMismatch,
+                    format!(
                         "page header start number mismatch: header starts on {}, first entry is {}",
-                        number,
+                        number.value,
                         self.current_entry.position().entry_number(),
-                    ));
-                }
+                    ),
+                )
+                .with_span(number.span);
+                self.error(label(
+                    error,
+                    self.current_entry_header,
+                    "first entry on this page",
+                ));
             }
-            // Parse the remaining lines of the entry
-            for line in lines {
-                if let Some(subject) = line.strip_prefix(SUBJECT_PREFIX) {
+        }
+        // Parse the remaining lines of the entry
+        for line in entry.lines() {
+            match *line {
+                LineNode::Subject { span, subject } => {
                     // This is the start of a new block
                     if self.multi_page_flag {
-                        self.error("continuation of multi-page entry started with a new subject line instead of a continuation marker".to_owned());
+                        self.error(
+                            Diagnostic::error(
+                                Code::MissingContinuation,
+                                "continuation of multi-page entry started with a new subject line instead of a continuation marker",
+                            )
+                            .with_span(span),
+                        );
                         self.multi_page_flag = false;
                     }
+                    let subject = self.token(subject).text.to_owned();
                     self.current_entry
                         .contents_mut()
-                        .push(Block::new(subject.to_owned(), String::new()));
-                } else {
+                        .push(Block::new(subject, String::new()));
+                }
+                LineNode::Text {
+                    span,
+                    continued,
+                    text,
+                    continues,
+                } => {
                     // Check for a continuation marker
-                    let line = if self.multi_page_flag {
-                        if let Some(line) = line.strip_prefix(&format!("{} ", MULTI_PAGE)) {
-                            line
-                        } else {
-                            self.error("no continuation marker after multi-page split".to_owned());
-                            line
-                        }
-                    } else {
-                        line
-                    };
-                    self.multi_page_flag = false;
+                    if self.multi_page_flag && continued.is_none() {
+                        self.error(
+                            Diagnostic::warning(
+                                Code::MissingContinuation,
+                                "no continuation marker after multi-page split",
+                            )
+                            .with_span(Span::new(span.start(), span.start())),
+                        );
+                    }
                     // Check for a new multi-page marker
-                    let line = if let Some(line) = line
-                        .strip_suffix(&format!(" {}", MULTI_PAGE))
-                        .or_else(|| line.strip_suffix(MULTI_PAGE))
-                    {
-                        // This is a multi-page entry
-                        self.multi_page_flag = true;
-                        line
-                    } else {
-                        line
-                    };
+                    self.multi_page_flag = continues.is_some();
                     // Add the line to the current block
+                    let text = self.token(text);
                     let block = self
                         .current_entry
                         .contents_mut()
                         .last_mut()
-                        .ok_or("no subject line")?
+                        .ok_or_else(|| {
+                            Diagnostic::error(Code::MissingSubject, "no subject line")
+                                .with_span(text.span())
+                        })?
                         .text_mut();
                     if !block.is_empty() {
                         block.push(' ');
                     }
-                    block.push_str(line);
+                    block.push_str(text.text);
                 }
             }
-            if !self.multi_page_flag {
-                // Finish recording this entry
-                self.read_entries.push(self.current_entry.clone());
-            }
+        }
+        // Record where this part of the entry was written
+        let page_entry = PageEntry::new(
+            self.current_entry.position().entry_number(),
+            self.current_entry.recorded_date(),
+            continued,
+            self.multi_page_flag,
+        );
+        if let Some(page) = self.pages.last_mut() {
+            page.entries_mut().push(page_entry);
+        }
+        if !self.multi_page_flag {
+            // Finish recording this entry
+            self.read_entries.push(self.current_entry.clone());
+        }
+        Ok(())
+    }
+
+    /// Parse a token into a date, noting its style
+    fn date(&mut self, token: Token, name: &str) -> Result<Date, Diagnostic> {
+        let (date, style) = parse_date(token, name, self.dialect)?;
+        if let Some(warning) = self.styles.date(style, token.span(), self.dialect) {
+            self.error(warning);
+        }
+        Ok(date)
+    }
+
+    /// Read the time zone line of a page header
+    fn page_zone(&mut self, span: Span) -> Result<(), Diagnostic> {
+        let line = self.token(span);
+        let mut words = line.split_ascii_whitespace();
+        let zone = expect_value(words.next(), "time zone", "page header", line)?;
+        self.page_zone = Some(parse_zone(zone, "page header time zone")?);
+        if let Some(extra) = words.next() {
+            self.error(
+                Diagnostic::warning(
+                    Code::TrailingText,
+                    format!(
+                        "Unexpected text after page header time zone: {}",
+                        extra.text
+                    ),
+                )
+                .with_span(extra.span()),
+            );
         }
         Ok(())
     }
 
+    /// Get the token for a span of the source text
+    fn token(&self, span: Span) -> Token<'a> {
+        Token::of(self.source, span)
+    }
+
     /// Record a potential error into the parser's memory
-    fn record<T>(&mut self, result: Result<T, String>) -> Option<T> {
+    fn record<T>(&mut self, result: Result<T, Diagnostic>) -> Option<T> {
         match result {
             Ok(value) => Some(value),
-            Err(message) => {
-                self.error(message);
+            Err(error) => {
+                self.error(error);
                 None
             }
         }
     }
 
     /// Record an error into the parser's memory
-    fn error(&mut self, error: String) {
-        self.errors.push(format!(
-            "{error}\nnear entry {}",
-            self.next_entry_position.entry_number()
-        ));
+    fn error(&mut self, error: Diagnostic) {
+        let note = format!("near entry {}", self.next_entry_position.entry_number());
+        let mut error = error.with_note(note);
+        // Relate the error to the entry being read, once there is one
+        if self.current_entry_header.is_some() {
+            error = error.with_mark(self.current_entry.position().clone());
+        }
+        self.errors.push(error);
     }
 }
 
@@ -438,16 +848,16 @@ enum PageHeaderExpectations {
     NewHeader,
     /// The page header was just read, check the first entry next
     StartAndEnd {
-        start_recorded_date: Date,
-        start_number: u32,
-        end_recorded_date: Date,
-        end_number: u32,
+        start_recorded_date: Spanned<Date>,
+        start_number: Spanned<u32>,
+        end_recorded_date: Spanned<Date>,
+        end_number: Spanned<u32>,
     },
     /// The first entry on the page has been confirmed, check the last entry on
     /// page end
     End {
-        end_recorded_date: Date,
-        end_number: u32,
+        end_recorded_date: Spanned<Date>,
+        end_number: Spanned<u32>,
     },
     /// There was an error parsing the previous page header, there are no
     /// constraints to apply but a new page header should not
@@ -455,92 +865,326 @@ enum PageHeaderExpectations {
     Error,
 }
 
-/// Separator between components (page boundaries, entries, page headers)
-const COMPONENT_SEPARATOR: &str = "\n\n";
+/// The styles dates and times have been written in so far, each along with the
+/// first value written in that style. The first style seen is taken to be the
+/// logbook's own
+#[derive(Debug, Default)]
+struct Styles {
+    date: Option<Spanned<DateStyle>>,
+    time: Option<Spanned<TimeStyle>>,
+}
+
+impl Styles {
+    /// Note a date written in a style, returning a warning if the logbook's
+    /// dates are written in another style
+    fn date(&mut self, style: DateStyle, span: Span, dialect: &Dialect) -> Option<Diagnostic> {
+        let first = *self.date.get_or_insert(Spanned::new(style, span));
+        (first.value != style).then(|| {
+            mixed_styles(
+                "date",
+                dialect.example_date(style),
+                dialect.example_date(first.value),
+                span,
+                first.span,
+            )
+        })
+    }
 
-/// Page boundary marker
-const PAGE_MARKER: &str = "-----";
+    /// Note a time written in a style, returning a warning if the logbook's
+    /// times are written in another style
+    fn time(&mut self, style: TimeStyle, span: Span, dialect: &Dialect) -> Option<Diagnostic> {
+        let first = *self.time.get_or_insert(Spanned::new(style, span));
+        (first.value != style).then(|| {
+            mixed_styles(
+                "time",
+                dialect.example_time(style),
+                dialect.example_time(first.value),
+                span,
+                first.span,
+            )
+        })
+    }
 
-/// Page header split between date and number range
-const PAGE_RANGE_SPLIT: &str = "\n";
+    /// Get the logbook's own styles, defaulting to the dialect's formats
+    fn style(&self) -> Style {
+        Style::new(
+            self.date.map(|date| date.value).unwrap_or_default(),
+            self.time.map(|time| time.value).unwrap_or_default(),
+        )
+    }
+}
 
-/// Page header date and number separator
-const PAGE_RANGE_SEPARATOR: &str = "-";
+/// Create a warning for a date or time written in a different style than the
+/// first one in the logbook, given examples of both styles
+fn mixed_styles(
+    kind: &str,
+    written: String,
+    expected: String,
+    span: Span,
+    first: Span,
+) -> Diagnostic {
+    Diagnostic::warning(
+        Code::MixedStyles,
+        format!(
+            "{kind} written like {written}, but this logbook's {kind}s are written like {expected}"
+        ),
+    )
+    .with_span(span)
+    .with_label(first, format!("first {kind} in this logbook"))
+}
 
-/// Entry header start
-const HEADER_START: &str = "Entry";
+/// A parsed value along with the span of source text it came from
+#[derive(Debug, Clone, Copy)]
+struct Spanned<T> {
+    value: T,
+    span: Span,
+}
 
-/// Separator between entry number and date in entry header
-const HEADER_POST_NUMBER: &str = ":";
+impl<T> Spanned<T> {
+    fn new(value: T, span: Span) -> Self {
+        Self { value, span }
+    }
+}
 
-/// Separator between entry date and start time in entry header
-const HEADER_STARTED: &str = "started";
+/// Separator between components (page boundaries, entries, page headers)
+pub(crate) const COMPONENT_SEPARATOR: &str = "\n\n";
 
-/// Separator between entry start and finished time in entry header
-const HEADER_FINISHED: &str = "finished";
+/// Page header split between date and number range
+pub(crate) const PAGE_RANGE_SPLIT: &str = "\n";
 
-/// Prefix for the subject lines of an entry
-const SUBJECT_PREFIX: &str = "    ";
+/// Parse a date written in a dialect's format or any other date style
+pub fn read_date(text: &str, dialect: &Dialect) -> Result<Date, Diagnostic> {
+    parse_date(Token::new(text, 0), "date", dialect).map(|(date, _)| date)
+}
 
-/// Marker for multi-page entries
-const MULTI_PAGE: &str = "(->)";
+/// Parse a token into a date, in whichever style it was written in. If it
+/// can't be read in any style, the error from reading it in the dialect's own
+/// style is returned
+fn parse_date(
+    token: Token,
+    name: &str,
+    dialect: &Dialect,
+) -> Result<(Date, DateStyle), Diagnostic> {
+    let mut first_error = None;
+    for style in DateStyle::ALL {
+        match dialect.read_date(token.text, style) {
+            Ok(date) => return Ok((date, style)),
+            Err(error) => {
+                first_error.get_or_insert(error);
+            }
+        }
+    }
+    let error = first_error.expect("there is at least one date style");
+    Err(Diagnostic::error(Code::BadDate, format!("bad {name} - {error}")).with_span(token.span()))
+}
 
-/// Start of the preamble entry rage
-const ENTRY_RANGE_START: &str = "Entries from";
+/// Read a time from the next words of an iterator, in whichever style it was
+/// written in, returning it with its style and span. If it can't be read in
+/// any style, the words of a time in the dialect's own style are skipped and
+/// the error from reading them is returned
+fn read_time<'a>(
+    words: &mut (impl Iterator<Item = Token<'a>> + Clone),
+    name: &str,
+    location: &str,
+    within: Token,
+    dialect: &Dialect,
+) -> Result<(Time, TimeStyle, Span), Diagnostic> {
+    let mut first_error = None;
+    for style in TimeStyle::ALL {
+        let mut attempt = words.clone();
+        let result = expect_values(
+            &mut attempt,
+            dialect.time_words_in(style),
+            name,
+            location,
+            within,
+        )
+        .and_then(|words| parse_time(&words, name, dialect, style));
+        match result {
+            Ok((time, span)) => {
+                *words = attempt;
+                return Ok((time, style, span));
+            }
+            Err(error) => {
+                first_error.get_or_insert((attempt, error));
+            }
+        }
+    }
+    let (attempt, error) = first_error.expect("there is at least one time style");
+    *words = attempt;
+    Err(error)
+}
 
-/// Separator between the date and entry number of the preamble entry range
-const ENTRY_RANGE_MARK_SEPARATOR: &str = "-";
+/// Parse the words of a time (such as the time and period) written in a style
+/// into a time, along with the span of the words
+fn parse_time(
+    words: &[Token],
+    name: &str,
+    dialect: &Dialect,
+    style: TimeStyle,
+) -> Result<(Time, Span), Diagnostic> {
+    let text = words
+        .iter()
+        .map(|word| word.text)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let span = match (words.first(), words.last()) {
+        (Some(first), Some(last)) => first.span().to(last.span()),
+        _ => Span::new(0, 0),
+    };
+    let time = dialect.read_time(&text, style).map_err(|error| {
+        Diagnostic::error(Code::BadTime, format!("bad {name} - {error}")).with_span(span)
+    })?;
+    Ok((time, span))
+}
 
-/// Separator between the start and end marks of the preamble entry range
-const ENTRY_RANGE_SEPARATOR: &str = "to";
+/// Read an optional time zone annotation from the next word of an iterator.
+/// Words that don't look like a zone are left for the caller, while words that
+/// look like one but aren't in the tz database are skipped and reported
+fn read_zone<'a>(
+    words: &mut (impl Iterator<Item = Token<'a>> + Clone),
+    name: &str,
+) -> Result<Option<Zone>, Diagnostic> {
+    match words.clone().next() {
+        Some(word) if Zone::parse(word.text).is_some() || Zone::resembles(word.text) => {
+            words.next();
+            parse_zone(word, name).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
 
-/// Placeholder for the ending entry number of the preamble entry range
-const ENTRY_RANGE_PLACEHOLDER: &str = "_";
+/// Parse a token into a time zone
+fn parse_zone(token: Token, name: &str) -> Result<Zone, Diagnostic> {
+    Zone::parse(token.text).ok_or_else(|| {
+        Diagnostic::error(
+            Code::BadZone,
+            format!(
+                "bad {name} - expected UTC, an offset like UTC+2 or a tz database name like Europe/Berlin, got {}",
+                token.text
+            ),
+        )
+        .with_span(token.span())
+    })
+}
 
-/// Hour before which an entry will be considered as being written on the
-/// previous day
-const NEXT_DAY: u8 = 6;
+/// Parse a token into a number
+fn parse_number(token: Token, name: &str) -> Result<u32, Diagnostic> {
+    token.text.parse::<u32>().map_err(|error| {
+        Diagnostic::error(Code::BadNumber, format!("bad {name} - {error}")).with_span(token.span())
+    })
+}
 
-/// Parse a string into a date
-fn parse_date(string: &str, name: &str) -> Result<Date, String> {
-    Date::parse(
-        string,
-        format_description!("[month padding:none]/[day padding:none]/[year]"),
-    )
-    .map_err(|error| format!("bad {name} - {error}"))
+/// The values given in a well-formed entry header
+pub(crate) struct EntryHeader {
+    pub(crate) number: u32,
+    pub(crate) date: Date,
+    pub(crate) started: Time,
+    pub(crate) finished: Time,
+    pub(crate) started_zone: Option<Zone>,
+    pub(crate) finished_zone: Option<Zone>,
 }
 
-/// Parse a string into a time
-fn parse_time(string: &str, name: &str) -> Result<Time, String> {
-    Time::parse(
-        string,
-        format_description!("[hour padding:none repr:12]:[minute padding:none] [period]"),
+/// Read the values of an entry header line written in a dialect, tolerating
+/// irregular spacing. Returns None if the line is not a well-formed entry
+/// header
+pub(crate) fn read_entry_header(line: &str, dialect: &Dialect) -> Option<EntryHeader> {
+    let line = Token::new(line, 0);
+    let mut words = line.split_ascii_whitespace();
+    expect_literal(
+        words.next(),
+        dialect.header_start(),
+        "entry header start",
+        line,
+    )
+    .ok()?;
+    let number = words.next()?.strip_suffix(dialect.header_post_number())?;
+    let date = words.next()?;
+    expect_literal(
+        words.next(),
+        dialect.header_started(),
+        "entry header started",
+        line,
     )
-    .map_err(|error| format!("bad {name} - {error}"))
+    .ok()?;
+    let (started, _, _) = read_time(&mut words, "entry started time", "", line, dialect).ok()?;
+    let started_zone = read_zone(&mut words, "entry started time zone").ok()?;
+    expect_literal(
+        words.next(),
+        dialect.header_finished(),
+        "entry header finished",
+        line,
+    )
+    .ok()?;
+    let (finished, _, _) = read_time(&mut words, "entry finished time", "", line, dialect).ok()?;
+    let finished_zone = read_zone(&mut words, "entry finished time zone").ok()?;
+    if words.next().is_some() {
+        return None;
+    }
+    Some(EntryHeader {
+        number: parse_number(number, "entry number").ok()?,
+        date: parse_date(date, "entry date", dialect).ok()?.0,
+        started,
+        finished,
+        started_zone,
+        finished_zone,
+    })
 }
 
-/// Parse a string into a number
-fn parse_number(string: &str, name: &str) -> Result<u32, String> {
-    string
-        .parse::<u32>()
-        .map_err(|error| format!("bad {name} - {error}"))
+/// Point a diagnostic at a related span, if it is known
+fn label(diagnostic: Diagnostic, span: Option<Span>, message: &str) -> Diagnostic {
+    match span {
+        Some(span) => diagnostic.with_label(span, message),
+        None => diagnostic,
+    }
 }
 
-/// Return the next item of an iterator, or an error if there is none
+/// Return the next item of an iterator, or an error pointing at the end of the
+/// containing text if there is none
 fn expect_value<'a>(
-    source: Option<&'a str>,
+    source: Option<Token<'a>>,
     name: &str,
     location: &str,
-) -> Result<&'a str, String> {
-    source.ok_or(format!("no {name} in {location}"))
+    within: Token,
+) -> Result<Token<'a>, Diagnostic> {
+    source.ok_or_else(|| {
+        Diagnostic::error(Code::MissingToken, format!("no {name} in {location}"))
+            .with_span(within.end())
+    })
+}
+
+/// Return the next few items of an iterator, or an error pointing at the end of
+/// the containing text if there are too few
+fn expect_values<'a>(
+    source: &mut impl Iterator<Item = Token<'a>>,
+    count: usize,
+    name: &str,
+    location: &str,
+    within: Token,
+) -> Result<Vec<Token<'a>>, Diagnostic> {
+    (0..count)
+        .map(|_| expect_value(source.next(), name, location, within))
+        .collect()
 }
 
 /// Expect a value as the next item of an iterator, returning an error if it
 /// doesn't match
-fn expect_literal(source: Option<&str>, value: &str, name: &str) -> Result<(), String> {
+fn expect_literal(
+    source: Option<Token>,
+    value: &str,
+    name: &str,
+    within: Token,
+) -> Result<(), Diagnostic> {
     match source {
-        Some(next) if next == value => Ok(()),
-        Some(other) => Err(format!("got {other} for {name}, expected {value}")),
-        None => Err(format!("no {name}, expected {value}")),
+        Some(next) if next.text == value => Ok(()),
+        Some(other) => Err(Diagnostic::error(
+            Code::UnexpectedToken,
+            format!("got {} for {name}, expected {value}", other.text),
+        )
+        .with_span(other.span())),
+        None => Err(
+            Diagnostic::error(Code::MissingToken, format!("no {name}, expected {value}"))
+                .with_span(within.end()),
+        ),
     }
 }
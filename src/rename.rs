@@ -0,0 +1,39 @@
+use crate::diagnostic::Diagnostic;
+use crate::dialect::Dialect;
+use crate::parse;
+use crate::subject::{Aliases, SubjectPath};
+use crate::syntax::{Edit, LineNode, SyntaxTree};
+use crate::write;
+
+/// Rename the subjects of a logbook written in a dialect to their canonical
+/// forms. Each subject line with an alias is rewritten as it would be written
+/// for the canonical subject, leaving every other line untouched. Logbooks
+/// that cannot be read are left alone and an error is returned
+pub fn rename_subjects(
+    logbook: &str,
+    aliases: &Aliases,
+    dialect: &Dialect,
+) -> Result<String, Diagnostic> {
+    let tree = SyntaxTree::with_dialect(logbook, dialect);
+    parse::parse_tree(&tree)?;
+    let mut edits = Vec::new();
+    let lines = tree
+        .pages()
+        .iter()
+        .flat_map(|page| page.entries())
+        .flat_map(|entry| entry.lines());
+    for line in lines {
+        let LineNode::Subject { span, subject } = *line else {
+            continue;
+        };
+        let subject = SubjectPath::parse(tree.text(subject));
+        let canonical = aliases.canonical(&subject);
+        if canonical != subject {
+            edits.push(Edit::new(
+                span,
+                write::write_subject_line(dialect, &canonical.to_string()),
+            ));
+        }
+    }
+    Ok(tree.edit(&edits))
+}
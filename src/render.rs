@@ -0,0 +1,198 @@
+use crate::diagnostic::{Diagnostic, Location, Severity, Span};
+use std::fmt::Write;
+
+/// ANSI style for errors
+const ERROR_STYLE: &str = "1;31";
+
+/// ANSI style for warnings
+const WARNING_STYLE: &str = "1;33";
+
+/// ANSI style for line numbers, gutters and secondary labels
+const GUTTER_STYLE: &str = "1;34";
+
+/// ANSI style for emphasized text
+const BOLD_STYLE: &str = "1";
+
+/// Width that tabs are expanded to when displaying source lines
+const TAB_WIDTH: usize = 4;
+
+/// Render a diagnostic in the style of rustc, showing the source lines it
+/// refers to with markers under the relevant text. Without source text, only
+/// the message, position and notes are shown
+pub fn render(diagnostic: &Diagnostic, source: Option<&str>, color: bool) -> String {
+    let paint = |text: &str, style: &str| {
+        if color {
+            format!("\x1b[{style}m{text}\x1b[0m")
+        } else {
+            text.to_owned()
+        }
+    };
+    let severity_style = match diagnostic.severity() {
+        Severity::Error => ERROR_STYLE,
+        Severity::Warning => WARNING_STYLE,
+    };
+    let mut rendered = String::new();
+    // Heading
+    let _ = writeln!(
+        rendered,
+        "{}{}",
+        paint(
+            &format!("{}[{}]", diagnostic.severity(), diagnostic.code()),
+            severity_style
+        ),
+        paint(&format!(": {}", diagnostic.message()), BOLD_STYLE),
+    );
+    // Collect the annotated spans, primary first
+    let annotations = source
+        .map(|source| {
+            diagnostic
+                .span()
+                .map(|span| Annotation::new(source, span, true, ""))
+                .into_iter()
+                .chain(
+                    diagnostic
+                        .labels()
+                        .iter()
+                        .map(|label| Annotation::new(source, label.span(), false, label.message())),
+                )
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let gutter_width = annotations
+        .iter()
+        .map(|annotation| annotation.location.line().to_string().len())
+        .max()
+        .unwrap_or(0);
+    let blank = " ".repeat(gutter_width);
+    let gutter = paint(&format!("{blank} |"), GUTTER_STYLE);
+    // Position
+    let location = diagnostic
+        .location()
+        .or_else(|| annotations.first().map(|annotation| annotation.location));
+    match (diagnostic.file(), location) {
+        (Some(file), Some(location)) => {
+            let _ = writeln!(
+                rendered,
+                "{blank}{} {}:{}",
+                paint("-->", GUTTER_STYLE),
+                file.display(),
+                location
+            );
+        }
+        (Some(file), None) => {
+            let _ = writeln!(
+                rendered,
+                "{blank}{} {}",
+                paint("-->", GUTTER_STYLE),
+                file.display()
+            );
+        }
+        (None, Some(location)) => {
+            let _ = writeln!(
+                rendered,
+                "{blank}{} {}",
+                paint("-->", GUTTER_STYLE),
+                location
+            );
+        }
+        (None, None) => (),
+    }
+    // Source lines, each followed by the markers on it
+    if !annotations.is_empty() {
+        let _ = writeln!(rendered, "{gutter}");
+        let mut lines = annotations
+            .iter()
+            .map(|annotation| annotation.location.line())
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+        let mut previous_line = None;
+        for line in lines {
+            if previous_line.is_some_and(|previous| line > previous + 1) {
+                let _ = writeln!(rendered, "{}", paint("...", GUTTER_STYLE));
+            }
+            previous_line = Some(line);
+            let mut on_line = annotations
+                .iter()
+                .filter(|annotation| annotation.location.line() == line)
+                .collect::<Vec<_>>();
+            on_line.sort_by_key(|annotation| annotation.column);
+            let _ = writeln!(
+                rendered,
+                "{} {}",
+                paint(&format!("{line:>gutter_width$} |"), GUTTER_STYLE),
+                on_line[0].line_text
+            );
+            for annotation in on_line {
+                let (marker, style) = if annotation.primary {
+                    ("^", severity_style)
+                } else {
+                    ("-", GUTTER_STYLE)
+                };
+                let markers = marker.repeat(annotation.width);
+                let text = if annotation.message.is_empty() {
+                    markers
+                } else {
+                    format!("{markers} {}", annotation.message)
+                };
+                let _ = writeln!(
+                    rendered,
+                    "{gutter} {}{}",
+                    " ".repeat(annotation.column),
+                    paint(&text, style)
+                );
+            }
+        }
+        if !diagnostic.notes().is_empty() {
+            let _ = writeln!(rendered, "{gutter}");
+        }
+    }
+    // Notes
+    for note in diagnostic.notes() {
+        let _ = writeln!(rendered, "{blank} {} {note}", paint("= note:", BOLD_STYLE));
+    }
+    rendered
+}
+
+/// A span of source text to be marked in a rendered diagnostic
+struct Annotation<'a> {
+    /// The location the span starts at
+    location: Location,
+    /// The full line of source text the span starts on, with tabs expanded
+    line_text: String,
+    /// The display column the markers start at, starting from 0
+    column: usize,
+    /// The number of markers to display
+    width: usize,
+    /// Whether this is the main span of the diagnostic
+    primary: bool,
+    /// The message to display after the markers
+    message: &'a str,
+}
+
+impl<'a> Annotation<'a> {
+    fn new(source: &str, span: Span, primary: bool, message: &'a str) -> Self {
+        let start = span.start().min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |index| index + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |index| start + index);
+        // Only mark the part of the span on its first line
+        let end = span.end().clamp(start, line_end);
+        Self {
+            location: Location::of(source, start),
+            line_text: source[line_start..line_end].replace('\t', &" ".repeat(TAB_WIDTH)),
+            column: display_width(&source[line_start..start]),
+            width: display_width(&source[start..end]).max(1),
+            primary,
+            message,
+        }
+    }
+}
+
+/// Get the number of columns a piece of text takes up when displayed
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|character| if character == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
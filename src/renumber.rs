@@ -0,0 +1,111 @@
+use crate::diagnostic::{Code, Diagnostic, Severity, Span};
+use crate::dialect::Dialect;
+use crate::parse;
+use crate::syntax::{Edit, SyntaxTree};
+use crate::{write, Logbook, Mark};
+
+/// Renumber the entries of a logbook written in a dialect from the first one
+/// on or after a mark's effective date, giving them consecutive entry numbers
+/// starting from the mark's. Entry headers, the number ranges of affected page
+/// headers and the preamble entry range are rewritten to match, leaving
+/// everything else untouched. Logbooks with entries that cannot be read are
+/// left alone and an error is returned
+pub fn renumber(logbook: &str, from: &Mark, dialect: &Dialect) -> Result<String, Diagnostic> {
+    let tree = SyntaxTree::with_dialect(logbook, dialect);
+    let (parsed, diagnostics) = parse::parse_tree(&tree)?.into_parts();
+    // Every entry header in the tree must correspond to a parsed entry
+    let headers = tree
+        .pages()
+        .iter()
+        .flat_map(|page| page.entries())
+        .filter_map(|entry| entry.header())
+        .collect::<Vec<_>>();
+    if headers.len() != parsed.len() {
+        return Err(diagnostics
+            .into_iter()
+            .find(|diagnostic| {
+                diagnostic.severity() == Severity::Error
+                    && !matches!(
+                        diagnostic.code(),
+                        Code::NumberMismatch | Code::PageHeaderMismatch | Code::EndMismatch
+                    )
+            })
+            .unwrap_or_else(|| {
+                Diagnostic::error(
+                    Code::MissingContinuation,
+                    "last entry is continued past the end of the logbook",
+                )
+                .with_span(Span::new(logbook.len(), logbook.len()))
+                .locate(logbook)
+            }));
+    }
+    let mut renumbered = parsed.clone();
+    renumbered.renumber(from);
+    Ok(tree.edit(&edits(&tree, &parsed, &renumbered)))
+}
+
+/// Find the edits that change the entry numbers in a tree from those of a
+/// parsed logbook to those of a renumbered copy of it
+fn edits(tree: &SyntaxTree, parsed: &Logbook, renumbered: &Logbook) -> Vec<Edit> {
+    let mut edits = Vec::new();
+    // Entry headers
+    let headers = tree
+        .pages()
+        .iter()
+        .flat_map(|page| page.entries())
+        .filter_map(|entry| entry.header());
+    for (header, (before, after)) in headers.zip(parsed.iter().zip(renumbered)) {
+        let number = after.position().entry_number();
+        if before.position().entry_number() == number {
+            continue;
+        }
+        if let Some(word) = header.words().get(1) {
+            let length = tree
+                .text(*word)
+                .strip_suffix(tree.dialect().header_post_number())
+                .map_or(word.range().len(), str::len);
+            edits.push(Edit::new(
+                Span::new(word.start(), word.start() + length),
+                number.to_string(),
+            ));
+        }
+    }
+    // Page header number ranges
+    let pages = tree
+        .pages()
+        .iter()
+        .zip(parsed.pages().iter().zip(renumbered.pages()));
+    for (node, (before, after)) in pages {
+        if let (Some(node), Some(before), Some(after)) =
+            (node.header(), before.header(), after.header())
+        {
+            if before != after {
+                edits.push(Edit::new(
+                    node.numbers().span(),
+                    write::write_number_range(
+                        tree.dialect(),
+                        after.first_number(),
+                        after.last_number(),
+                    ),
+                ));
+            }
+        }
+    }
+    // Preamble entry range numbers
+    if let Some(range) = tree.preamble().entry_range() {
+        let marks = [
+            (2, Some(parsed.start()), Some(renumbered.start())),
+            (6, parsed.end(), renumbered.end()),
+        ];
+        for (word, before, after) in marks {
+            if let (Some(word), Some(before), Some(after)) =
+                (range.words().get(word), before, after)
+            {
+                if before.entry_number() != after.entry_number() {
+                    edits.push(Edit::new(*word, after.entry_number().to_string()));
+                }
+            }
+        }
+    }
+    edits
+}
@@ -0,0 +1,265 @@
+use crate::subject::SubjectPath;
+use crate::{Block, Entry, Logbook};
+use regex::{Regex, RegexBuilder};
+use std::ops::Range;
+use time::Date;
+
+/// Bytes of text to show on either side of a match, extended to the nearest
+/// whitespace so that words aren't cut
+const CONTEXT: usize = 40;
+
+/// Marker for text left out before or after the context of a match
+const ELLIPSIS: &str = "...";
+
+/// ANSI style for the line describing a matching block
+const HEADING_STYLE: &str = "1";
+
+/// ANSI style for matched text
+const MATCH_STYLE: &str = "1;31";
+
+/// What to look for in the text of blocks
+#[derive(Debug, Clone)]
+pub struct Query {
+    /// Patterns that must all match somewhere in a block's text
+    patterns: Vec<Regex>,
+}
+
+/// A query and the blocks to look for it in
+#[derive(Debug, Clone)]
+pub struct Search {
+    /// What to look for
+    query: Query,
+    /// Only look in blocks with this subject or one within it
+    subject: Option<SubjectPath>,
+    /// Only look in entries for this effective date or later
+    from: Option<Date>,
+    /// Only look in entries for this effective date or earlier
+    to: Option<Date>,
+}
+
+/// A block whose text matches a query
+#[derive(Debug, Clone)]
+pub struct Hit<'a> {
+    /// The entry the block is in
+    entry: &'a Entry,
+    /// The matching block
+    block: &'a Block,
+    /// The byte ranges of the block's text that matched, in order and not
+    /// overlapping
+    matches: Vec<Range<usize>>,
+}
+
+impl Query {
+    /// Create a query for some words, each of which must appear in a block.
+    /// Words in double quotes are a phrase that must appear together, in
+    /// order, with any whitespace between them
+    pub fn words(query: &str, ignore_case: bool) -> Result<Self, String> {
+        let mut patterns = Vec::new();
+        for (index, part) in query.split('"').enumerate() {
+            // Every other part is between quotes
+            let words = part.split_whitespace().map(regex::escape);
+            if index % 2 == 1 {
+                let phrase = words.collect::<Vec<_>>();
+                if !phrase.is_empty() {
+                    patterns.push(phrase.join(r"\s+"));
+                }
+            } else {
+                patterns.extend(words);
+            }
+        }
+        if patterns.is_empty() {
+            return Err("query has no words".to_owned());
+        }
+        Self::compile(patterns, ignore_case)
+    }
+
+    /// Create a query for a regular expression, which must match in a block
+    pub fn regex(query: &str, ignore_case: bool) -> Result<Self, String> {
+        Self::compile(vec![query.to_owned()], ignore_case)
+    }
+
+    /// Compile each pattern of a query
+    fn compile(patterns: Vec<String>, ignore_case: bool) -> Result<Self, String> {
+        let patterns = patterns
+            .iter()
+            .map(|pattern| {
+                RegexBuilder::new(pattern)
+                    .case_insensitive(ignore_case)
+                    .build()
+                    .map_err(|error| error.to_string())
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { patterns })
+    }
+
+    /// Find where a query matches in some text, if every pattern matches.
+    /// Overlapping matches are joined, and empty matches are ignored
+    pub fn find(&self, text: &str) -> Option<Vec<Range<usize>>> {
+        let mut matches = Vec::new();
+        for pattern in &self.patterns {
+            let found = pattern
+                .find_iter(text)
+                .filter(|found| !found.is_empty())
+                .map(|found| found.range())
+                .collect::<Vec<_>>();
+            if found.is_empty() {
+                return None;
+            }
+            matches.extend(found);
+        }
+        Some(join(matches))
+    }
+}
+
+impl Search {
+    /// Create a search for a query in every block
+    pub fn new(query: Query) -> Self {
+        Self {
+            query,
+            subject: None,
+            from: None,
+            to: None,
+        }
+    }
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+    pub fn subject(&self) -> Option<&SubjectPath> {
+        self.subject.as_ref()
+    }
+    pub fn set_subject(&mut self, subject: Option<SubjectPath>) {
+        self.subject = subject;
+    }
+    pub fn from(&self) -> Option<Date> {
+        self.from
+    }
+    pub fn set_from(&mut self, from: Option<Date>) {
+        self.from = from;
+    }
+    pub fn to(&self) -> Option<Date> {
+        self.to
+    }
+    pub fn set_to(&mut self, to: Option<Date>) {
+        self.to = to;
+    }
+
+    /// Find the blocks of some logbooks that match, in order
+    pub fn find<'a>(&self, logbooks: &'a [Logbook]) -> Vec<Hit<'a>> {
+        logbooks
+            .iter()
+            .flat_map(|logbook| logbook.iter())
+            .filter(|entry| {
+                let date = entry.position().effective_date();
+                self.from.is_none_or(|from| date >= from) && self.to.is_none_or(|to| date <= to)
+            })
+            .flat_map(|entry| entry.contents().iter().map(move |block| (entry, block)))
+            .filter(|(_, block)| {
+                self.subject
+                    .as_ref()
+                    .is_none_or(|subject| subject.contains(block.subject_path()))
+            })
+            .filter_map(|(entry, block)| {
+                let matches = self.query.find(block.text())?;
+                Some(Hit {
+                    entry,
+                    block,
+                    matches,
+                })
+            })
+            .collect()
+    }
+}
+
+impl<'a> Hit<'a> {
+    pub fn entry(&self) -> &'a Entry {
+        self.entry
+    }
+    pub fn block(&self) -> &'a Block {
+        self.block
+    }
+    /// Get the byte ranges of the block's text that matched
+    pub fn matches(&self) -> &[Range<usize>] {
+        &self.matches
+    }
+
+    /// Render the entry number, effective date and subject of the block,
+    /// followed by a line for each part of its text with matches, with the
+    /// matched text highlighted
+    pub fn render(&self, color: bool) -> String {
+        let paint = |text: &str, style: &str| {
+            if color {
+                format!("\x1b[{style}m{text}\x1b[0m")
+            } else {
+                text.to_owned()
+            }
+        };
+        let text = self.block.text().as_str();
+        let mut rendered = paint(
+            &format!(
+                "{} #{} {}",
+                self.entry.position().effective_date(),
+                self.entry.position().entry_number(),
+                self.block.subject()
+            ),
+            HEADING_STYLE,
+        );
+        rendered.push('\n');
+        let windows = join(
+            self.matches
+                .iter()
+                .map(|found| window(text, found))
+                .collect(),
+        );
+        for window in windows {
+            rendered.push_str("    ");
+            if window.start > 0 {
+                rendered.push_str(ELLIPSIS);
+            }
+            let mut position = window.start;
+            for found in self
+                .matches
+                .iter()
+                .filter(|found| window.start <= found.start && found.end <= window.end)
+            {
+                rendered.push_str(&text[position..found.start]);
+                rendered.push_str(&paint(&text[found.clone()], MATCH_STYLE));
+                position = found.end;
+            }
+            rendered.push_str(&text[position..window.end]);
+            if window.end < text.len() {
+                rendered.push_str(ELLIPSIS);
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
+}
+
+/// Get the part of some text to show around a match, starting and ending at
+/// whitespace at least the context length away where possible
+fn window(text: &str, found: &Range<usize>) -> Range<usize> {
+    let limit = found.start.saturating_sub(CONTEXT);
+    let start = text[..found.start]
+        .char_indices()
+        .rev()
+        .find(|(index, character)| *index < limit && character.is_whitespace())
+        .map_or(0, |(index, character)| index + character.len_utf8());
+    let end = text[found.end..]
+        .char_indices()
+        .find(|(index, character)| *index >= CONTEXT && character.is_whitespace())
+        .map_or(text.len(), |(index, _)| found.end + index);
+    start..end
+}
+
+/// Sort ranges and join those that overlap
+fn join(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_by_key(|range| range.start);
+    let mut joined = Vec::<Range<usize>>::new();
+    for range in ranges {
+        match joined.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => joined.push(range),
+        }
+    }
+    joined
+}
@@ -0,0 +1,340 @@
+use crate::{Entry, Logbook};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use time::{Date, Duration, PrimitiveDateTime, Time};
+
+/// Number of most frequent subjects to report
+const TOP_SUBJECTS: usize = 10;
+
+/// Number of longest gaps between entries to report
+const LONGEST_GAPS: usize = 3;
+
+/// Percentile reported alongside the mean and median
+const PERCENTILE: f64 = 0.9;
+
+/// How to group entries when computing statistics
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Grouping {
+    /// All entries together
+    #[default]
+    All,
+    /// Entries by the month of their effective date
+    Month,
+    /// Entries by the year of their effective date
+    Year,
+}
+
+/// Statistics over a group of entries
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// The month ("2000-01") or year ("2000") of the entries, if grouped
+    label: Option<String>,
+    /// The number of entries
+    entries: usize,
+    /// Words of text in each entry
+    words_per_entry: Option<Summary>,
+    /// Words of text in each block
+    words_per_block: Option<Summary>,
+    /// Blocks in each entry
+    blocks_per_entry: Option<Summary>,
+    /// Minutes taken to write each entry
+    duration: Option<Summary>,
+    /// Total time taken to write every entry
+    total_duration: Duration,
+    /// Average time of day entries were started at
+    average_start: Option<Time>,
+    /// The most frequent subjects and how many blocks have each, most frequent
+    /// first
+    top_subjects: Vec<(String, usize)>,
+    /// The longest run of consecutive effective dates with entries
+    longest_streak: Option<DateRange>,
+    /// The longest runs of effective dates without entries between the first
+    /// and last, longest first
+    longest_gaps: Vec<DateRange>,
+}
+
+/// The mean, median and 90th percentile of a series of values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    mean: f64,
+    median: f64,
+    percentile: f64,
+}
+
+/// An inclusive range of dates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateRange {
+    first: Date,
+    last: Date,
+}
+
+/// Compute statistics over the entries of several logbooks, in groups by
+/// effective date ordered from earliest to latest
+pub fn stats(logbooks: &[Logbook], grouping: Grouping) -> Vec<Stats> {
+    let mut groups = BTreeMap::<_, Vec<_>>::new();
+    for logbook in logbooks {
+        for entry in logbook {
+            let date = entry.position().effective_date();
+            let key = match grouping {
+                Grouping::All => None,
+                Grouping::Month => Some((date.year(), Some(u8::from(date.month())))),
+                Grouping::Year => Some((date.year(), None)),
+            };
+            groups.entry(key).or_default().push(entry);
+        }
+    }
+    if groups.is_empty() && grouping == Grouping::All {
+        groups.insert(None, Vec::new());
+    }
+    groups
+        .into_iter()
+        .map(|(key, entries)| {
+            let label = key.map(|(year, month)| match month {
+                Some(month) => format!("{year}-{month:02}"),
+                None => year.to_string(),
+            });
+            Stats::new(label, &entries)
+        })
+        .collect()
+}
+
+impl Stats {
+    /// Compute statistics over entries
+    pub fn new(label: Option<String>, entries: &[&Entry]) -> Self {
+        let words_per_entry = entries
+            .iter()
+            .map(|entry| entry.word_count() as f64)
+            .collect::<Vec<_>>();
+        let words_per_block = entries
+            .iter()
+            .flat_map(|entry| entry.contents())
+            .map(|block| block.word_count() as f64)
+            .collect::<Vec<_>>();
+        let blocks_per_entry = entries
+            .iter()
+            .map(|entry| entry.contents().len() as f64)
+            .collect::<Vec<_>>();
+        let durations = entries
+            .iter()
+            .map(|entry| entry.duration())
+            .collect::<Vec<_>>();
+        let minutes = durations
+            .iter()
+            .map(|duration| duration.as_seconds_f64() / 60.0)
+            .collect::<Vec<_>>();
+        // Count subjects, most frequent first and then by name
+        let mut subjects = HashMap::<&str, usize>::new();
+        for block in entries.iter().flat_map(|entry| entry.contents()) {
+            *subjects.entry(block.subject()).or_default() += 1;
+        }
+        let mut top_subjects = subjects
+            .into_iter()
+            .map(|(subject, count)| (subject.to_owned(), count))
+            .collect::<Vec<_>>();
+        top_subjects.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        top_subjects.truncate(TOP_SUBJECTS);
+        // Find runs of consecutive dates, and the gaps between them
+        let dates = entries
+            .iter()
+            .map(|entry| entry.position().effective_date())
+            .collect::<BTreeSet<_>>();
+        let mut streaks = Vec::<DateRange>::new();
+        for date in dates {
+            match streaks.last_mut() {
+                Some(streak) if streak.last + Duration::DAY == date => streak.last = date,
+                _ => streaks.push(DateRange::new(date, date)),
+            }
+        }
+        let mut longest_gaps = streaks
+            .windows(2)
+            .map(|pair| DateRange::new(pair[0].last + Duration::DAY, pair[1].first - Duration::DAY))
+            .collect::<Vec<_>>();
+        // Sorting is stable, so equally long gaps stay earliest first
+        longest_gaps.sort_by_key(|gap| std::cmp::Reverse(gap.days()));
+        longest_gaps.truncate(LONGEST_GAPS);
+        let longest_streak = streaks
+            .iter()
+            .rev()
+            .max_by_key(|streak| streak.days())
+            .copied();
+        Self {
+            label,
+            entries: entries.len(),
+            words_per_entry: Summary::of(words_per_entry),
+            words_per_block: Summary::of(words_per_block),
+            blocks_per_entry: Summary::of(blocks_per_entry),
+            duration: Summary::of(minutes),
+            total_duration: durations.iter().sum(),
+            average_start: average_start(entries),
+            top_subjects,
+            longest_streak,
+            longest_gaps,
+        }
+    }
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+    pub fn words_per_entry(&self) -> Option<Summary> {
+        self.words_per_entry
+    }
+    pub fn words_per_block(&self) -> Option<Summary> {
+        self.words_per_block
+    }
+    pub fn blocks_per_entry(&self) -> Option<Summary> {
+        self.blocks_per_entry
+    }
+    /// Get a summary of the minutes taken to write each entry
+    pub fn duration(&self) -> Option<Summary> {
+        self.duration
+    }
+    pub fn total_duration(&self) -> Duration {
+        self.total_duration
+    }
+    pub fn average_start(&self) -> Option<Time> {
+        self.average_start
+    }
+    pub fn top_subjects(&self) -> &[(String, usize)] {
+        &self.top_subjects
+    }
+    pub fn longest_streak(&self) -> Option<DateRange> {
+        self.longest_streak
+    }
+    pub fn longest_gaps(&self) -> &[DateRange] {
+        &self.longest_gaps
+    }
+}
+
+impl Summary {
+    /// Summarize a series of values, if there are any
+    pub fn of(mut values: Vec<f64>) -> Option<Self> {
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        let count = values.len();
+        let middle = count / 2;
+        let median = if count.is_multiple_of(2) {
+            (values[middle - 1] + values[middle]) / 2.0
+        } else {
+            values[middle]
+        };
+        // Nearest-rank percentile
+        let rank = (PERCENTILE * count as f64).ceil() as usize;
+        Some(Self {
+            mean: values.iter().sum::<f64>() / count as f64,
+            median,
+            percentile: values[rank.clamp(1, count) - 1],
+        })
+    }
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+    pub fn median(&self) -> f64 {
+        self.median
+    }
+    /// Get the 90th percentile
+    pub fn percentile(&self) -> f64 {
+        self.percentile
+    }
+}
+
+impl DateRange {
+    pub fn new(first: Date, last: Date) -> Self {
+        Self { first, last }
+    }
+    pub fn first(&self) -> Date {
+        self.first
+    }
+    pub fn last(&self) -> Date {
+        self.last
+    }
+    /// Get the number of days in this range
+    pub fn days(&self) -> i64 {
+        (self.last - self.first).whole_days() + 1
+    }
+}
+
+/// Get the average time of day entries were started at. Times are measured
+/// from midnight at the start of each entry's effective date, so that entries
+/// written just before and just after midnight average to around midnight
+/// rather than noon
+fn average_start(entries: &[&Entry]) -> Option<Time> {
+    if entries.is_empty() {
+        return None;
+    }
+    let total = entries
+        .iter()
+        .map(|entry| {
+            entry.started()
+                - PrimitiveDateTime::new(entry.position().effective_date(), Time::MIDNIGHT)
+        })
+        .sum::<Duration>();
+    Some(Time::MIDNIGHT + total / entries.len() as u32)
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => writeln!(f, "Entries in {label}: {}", self.entries)?,
+            None => writeln!(f, "Entries: {}", self.entries)?,
+        }
+        let summaries = [
+            ("Words per entry", self.words_per_entry),
+            ("Words per block", self.words_per_block),
+            ("Blocks per entry", self.blocks_per_entry),
+        ];
+        for (name, summary) in summaries {
+            if let Some(summary) = summary {
+                writeln!(
+                    f,
+                    "{name}: mean {:.1}, median {:.1}, 90th percentile {:.1}",
+                    summary.mean, summary.median, summary.percentile
+                )?;
+            }
+        }
+        if let Some(duration) = self.duration {
+            let minutes = |minutes: f64| Duration::minutes(minutes.round() as i64);
+            writeln!(
+                f,
+                "Duration: total {}, mean {}, median {}, 90th percentile {}",
+                self.total_duration,
+                minutes(duration.mean),
+                minutes(duration.median),
+                minutes(duration.percentile),
+            )?;
+        }
+        if let Some(start) = self.average_start {
+            writeln!(
+                f,
+                "Average start time: {:02}:{:02}",
+                start.hour(),
+                start.minute()
+            )?;
+        }
+        if let Some(streak) = self.longest_streak {
+            writeln!(f, "Longest streak: {streak}")?;
+        }
+        if !self.longest_gaps.is_empty() {
+            writeln!(f, "Longest gaps:")?;
+            for gap in &self.longest_gaps {
+                writeln!(f, "    {gap}")?;
+            }
+        }
+        if !self.top_subjects.is_empty() {
+            writeln!(f, "Top subjects:")?;
+            for (subject, count) in &self.top_subjects {
+                writeln!(f, "    {count:>5} {subject}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DateRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} day(s), {} to {}", self.days(), self.first, self.last)
+    }
+}
@@ -0,0 +1,355 @@
+use crate::diagnostic::{Code, Diagnostic, Span};
+use crate::{dialect, parse, Block, Entry, Logbook, Mark};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use time::Date;
+
+/// Separator between the levels of a subject
+pub const SEPARATOR: char = '/';
+
+/// A subject split into its levels ("Entries/Format" is "Format" within
+/// "Entries")
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SubjectPath {
+    /// Each level of the subject, outermost first
+    segments: Vec<String>,
+}
+
+/// Old subjects mapped to the canonical subjects that replace them. A subject
+/// within an old subject is moved along with it, so aliasing "Work" to "Job"
+/// turns "Work/Meetings" into "Job/Meetings"
+#[derive(Debug, Default, Clone)]
+pub struct Aliases {
+    /// The canonical subject for each old subject
+    aliases: BTreeMap<SubjectPath, SubjectPath>,
+}
+
+/// Every subject used in some logbooks, arranged by level
+#[derive(Debug, Default, Clone)]
+pub struct SubjectTree {
+    /// The root of the tree, for the empty subject
+    root: SubjectNode,
+}
+
+/// A subject within a subject tree, along with the subjects within it
+#[derive(Debug, Default, Clone)]
+pub struct SubjectNode {
+    /// The last level of this subject
+    name: String,
+    /// The number of blocks with exactly this subject
+    count: usize,
+    /// The number of blocks with this subject or any subject within it
+    total: usize,
+    /// The earliest entry with this subject or any subject within it
+    first: Option<Mark>,
+    /// The latest entry with this subject or any subject within it
+    last: Option<Mark>,
+    /// The subjects directly within this one, by name
+    children: BTreeMap<String, SubjectNode>,
+}
+
+impl SubjectPath {
+    /// Split a subject into its levels. Whitespace around each level is
+    /// ignored, as are empty levels
+    pub fn parse(subject: &str) -> Self {
+        Self {
+            segments: subject
+                .split(SEPARATOR)
+                .map(str::trim)
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        }
+    }
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+    /// Get the last level of this subject, if it isn't empty
+    pub fn name(&self) -> Option<&str> {
+        self.segments.last().map(String::as_str)
+    }
+    /// Get the subject this one is directly within, if it isn't empty
+    pub fn parent(&self) -> Option<SubjectPath> {
+        let (_, parent) = self.segments.split_last()?;
+        Some(Self {
+            segments: parent.to_vec(),
+        })
+    }
+    /// Check if a subject is this one or within it. The empty subject contains
+    /// every subject
+    pub fn contains(&self, subject: &SubjectPath) -> bool {
+        subject.segments.starts_with(&self.segments)
+    }
+}
+
+impl fmt::Display for SubjectPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.segments.join(&SEPARATOR.to_string()))
+    }
+}
+
+impl From<&str> for SubjectPath {
+    fn from(subject: &str) -> Self {
+        Self::parse(subject)
+    }
+}
+
+impl Aliases {
+    /// Create an empty set of aliases, which leaves every subject alone
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read aliases from a file
+    pub fn load(file: &Path) -> Result<Self, Diagnostic> {
+        let text = parse::read_file(file)?;
+        Self::parse(&text).map_err(|error| error.with_file(file))
+    }
+
+    /// Read aliases from text written like a dialect configuration, with each
+    /// line giving an alias as `old subject = canonical subject`
+    pub fn parse(config: &str) -> Result<Self, Diagnostic> {
+        let mut aliases = Self::default();
+        let mut offset = 0;
+        for line in config.split_inclusive('\n') {
+            let span = Span::new(offset, offset + line.trim_end().len());
+            offset += line.len();
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let bad = |message: String| {
+                Diagnostic::error(Code::BadConfig, message)
+                    .with_span(span)
+                    .locate(config)
+            };
+            let (old, canonical) = line
+                .split_once('=')
+                .ok_or_else(|| bad("expected an alias as `old = canonical`".to_owned()))?;
+            let old = dialect::unquote(old.trim()).map_err(bad)?;
+            let canonical = dialect::unquote(canonical.trim()).map_err(bad)?;
+            aliases
+                .add(SubjectPath::parse(&old), SubjectPath::parse(&canonical))
+                .map_err(bad)?;
+        }
+        Ok(aliases)
+    }
+
+    /// Alias an old subject to a canonical one. Neither may be empty, an old
+    /// subject may only be aliased once, and aliases may not lead back to
+    /// where they started
+    pub fn add(&mut self, old: SubjectPath, canonical: SubjectPath) -> Result<(), String> {
+        if old.segments.is_empty() || canonical.segments.is_empty() {
+            return Err("aliased subjects must not be empty".to_owned());
+        }
+        if let Some(existing) = self.aliases.get(&old) {
+            return Err(format!("{old} is already aliased to {existing}"));
+        }
+        self.aliases.insert(old.clone(), canonical);
+        if self.resolve(&old).is_none() {
+            self.aliases.remove(&old);
+            return Err(format!("aliases of {old} lead back to it"));
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Iterate over the old subjects and their canonical subjects, in order of
+    /// old subject
+    pub fn iter(&self) -> impl Iterator<Item = (&SubjectPath, &SubjectPath)> {
+        self.aliases.iter()
+    }
+
+    /// Get the canonical form of a subject. Aliases are followed one after
+    /// another, so an old subject may be aliased to another old subject
+    pub fn canonical(&self, subject: &SubjectPath) -> SubjectPath {
+        self.resolve(subject).unwrap_or_else(|| subject.clone())
+    }
+
+    /// Replace the subject of every block of some logbooks that has an alias
+    /// with its canonical subject
+    pub fn apply(&self, logbooks: &mut [Logbook]) {
+        if self.is_empty() {
+            return;
+        }
+        for entry in logbooks.iter_mut().flat_map(|logbook| &mut logbook.entries) {
+            for block in entry.contents_mut() {
+                let canonical = self.canonical(block.subject_path());
+                if canonical != *block.subject_path() {
+                    block.set_subject(canonical.to_string());
+                }
+            }
+        }
+    }
+
+    /// Follow the aliases of a subject to its canonical form, or None if they
+    /// go round in circles
+    fn resolve(&self, subject: &SubjectPath) -> Option<SubjectPath> {
+        let mut subject = subject.clone();
+        // Each alias can be used at most once on the way to a canonical subject
+        for _ in 0..=self.aliases.len() {
+            match self.step(&subject) {
+                Some(next) if next != subject => subject = next,
+                _ => return Some(subject),
+            }
+        }
+        None
+    }
+
+    /// Apply the alias of the longest old subject containing a subject
+    fn step(&self, subject: &SubjectPath) -> Option<SubjectPath> {
+        let (old, canonical) = self
+            .aliases
+            .iter()
+            .filter(|(old, _)| old.contains(subject))
+            .max_by_key(|(old, _)| old.segments.len())?;
+        let mut segments = canonical.segments.clone();
+        segments.extend_from_slice(&subject.segments[old.segments.len()..]);
+        Some(SubjectPath { segments })
+    }
+}
+
+impl SubjectTree {
+    /// Arrange the subjects of every block of some logbooks
+    pub fn new(logbooks: &[Logbook]) -> Self {
+        let mut tree = Self::default();
+        for logbook in logbooks {
+            for entry in logbook {
+                for block in entry.contents() {
+                    tree.add(block.subject_path(), entry.position());
+                }
+            }
+        }
+        tree
+    }
+    pub fn root(&self) -> &SubjectNode {
+        &self.root
+    }
+    /// Count a block with a subject, written in the entry at a mark
+    pub fn add(&mut self, subject: &SubjectPath, mark: &Mark) {
+        let mut node = &mut self.root;
+        node.see(mark);
+        for segment in subject.segments() {
+            node = node
+                .children
+                .entry(segment.clone())
+                .or_insert_with(|| SubjectNode::new(segment.clone()));
+            node.see(mark);
+        }
+        node.count += 1;
+    }
+    /// Find a subject in the tree
+    pub fn find(&self, subject: &SubjectPath) -> Option<&SubjectNode> {
+        subject
+            .segments()
+            .iter()
+            .try_fold(&self.root, |node, segment| node.children.get(segment))
+    }
+}
+
+impl SubjectNode {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Self::default()
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn count(&self) -> usize {
+        self.count
+    }
+    pub fn total(&self) -> usize {
+        self.total
+    }
+    pub fn first(&self) -> Option<&Mark> {
+        self.first.as_ref()
+    }
+    pub fn last(&self) -> Option<&Mark> {
+        self.last.as_ref()
+    }
+    /// Iterate over the subjects directly within this one, in order of name
+    pub fn children(&self) -> impl Iterator<Item = &SubjectNode> {
+        self.children.values()
+    }
+    /// Count a block with this subject or a subject within it
+    fn see(&mut self, mark: &Mark) {
+        self.total += 1;
+        if self
+            .first
+            .as_ref()
+            .is_none_or(|first| key(mark) < key(first))
+        {
+            self.first = Some(mark.clone());
+        }
+        if self.last.as_ref().is_none_or(|last| key(mark) > key(last)) {
+            self.last = Some(mark.clone());
+        }
+    }
+    /// Render this subject's line after a prefix, then the subjects within it
+    /// indented by another prefix
+    fn write(&self, f: &mut fmt::Formatter<'_>, first: &str, rest: &str) -> fmt::Result {
+        write!(f, "{first}{} ({}", self.name, self.total)?;
+        if self.count != self.total {
+            write!(f, ", {} directly", self.count)?;
+        }
+        let mark = |mark: &Mark| format!("{} #{}", mark.effective_date(), mark.entry_number());
+        match (&self.first, &self.last) {
+            (Some(earliest), Some(latest)) if earliest != latest => {
+                write!(f, ", {} to {}", mark(earliest), mark(latest))?;
+            }
+            (Some(only), _) => write!(f, ", {}", mark(only))?,
+            _ => (),
+        }
+        writeln!(f, ")")?;
+        let mut children = self.children().peekable();
+        while let Some(child) = children.next() {
+            if children.peek().is_some() {
+                child.write(f, &format!("{rest}├── "), &format!("{rest}│   "))?;
+            } else {
+                child.write(f, &format!("{rest}└── "), &format!("{rest}    "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SubjectTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in self.root.children() {
+            write!(f, "{child}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Renders a subject and every subject within it as an indented tree
+impl fmt::Display for SubjectNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, "", "")
+    }
+}
+
+/// Iterate over the blocks of some logbooks whose subjects are a subject or
+/// within it, along with their entries
+pub fn matching<'a>(
+    logbooks: &'a [Logbook],
+    subject: &'a SubjectPath,
+) -> impl Iterator<Item = (&'a Entry, &'a Block)> {
+    logbooks
+        .iter()
+        .flat_map(|logbook| logbook.iter())
+        .flat_map(|entry| entry.contents().iter().map(move |block| (entry, block)))
+        .filter(|(_, block)| subject.contains(block.subject_path()))
+}
+
+/// Get a key ordering marks by effective date, then by entry number
+fn key(mark: &Mark) -> (Date, u32) {
+    (mark.effective_date(), mark.entry_number())
+}
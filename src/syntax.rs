@@ -0,0 +1,613 @@
+use crate::diagnostic::Span;
+use crate::dialect::Dialect;
+use crate::parse::{self, PAGE_RANGE_SPLIT};
+
+/// Number of words in a preamble entry range after its start
+/// ("1/1/2000 - 1 to 1/3/2000 - 3")
+pub(crate) const ENTRY_RANGE_WORDS: usize = 7;
+
+/// A lossless syntax tree of a logbook. Every byte of the source text belongs
+/// either to the preamble or to exactly one page, and every byte of a page
+/// belongs to its marker, its header, one of its entries or the trivia between
+/// them. Nothing is interpreted beyond splitting text into its parts, so the
+/// tree can be built from any text and malformed parts are kept for the parser
+/// to report
+#[derive(Debug, Clone)]
+pub struct SyntaxTree<'a> {
+    /// The text this tree was built from
+    source: &'a str,
+    /// The dialect the text is written in
+    dialect: Dialect,
+    /// Everything before the first page marker
+    preamble: PreambleNode,
+    /// The pages, each running from its marker up to the next one
+    pages: Vec<PageNode>,
+}
+
+/// A replacement for a span of source text
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edit {
+    /// The text to replace
+    span: Span,
+    /// The text to replace it with
+    replacement: String,
+}
+
+/// The preamble of a logbook
+#[derive(Debug, Clone)]
+pub struct PreambleNode {
+    /// The whole preamble, including any prose around the entry range and the
+    /// blank lines before the first page marker
+    span: Span,
+    /// The entry range, if one was found
+    entry_range: Option<EntryRangeNode>,
+    /// The declaration of when days start, if one was found
+    day_start: Option<DayStartNode>,
+}
+
+/// The entry range of a preamble ("Entries from 1/1/2000 - 1 to _ - _")
+#[derive(Debug, Clone)]
+pub struct EntryRangeNode {
+    /// From the start of the entry range to its last word
+    span: Span,
+    /// The words after the start of the entry range, which may be fewer than
+    /// expected
+    words: Vec<Span>,
+}
+
+/// The declaration of the hour before which entries count for the previous
+/// day ("Days start at 12:00 PM")
+#[derive(Debug, Clone)]
+pub struct DayStartNode {
+    /// From the start of the declaration to the end of its line
+    span: Span,
+    /// The words after the start of the declaration
+    words: Vec<Span>,
+}
+
+/// A page of a logbook
+#[derive(Debug, Clone)]
+pub struct PageNode {
+    /// From the page marker up to the next page marker or the end of the text
+    span: Span,
+    /// The page marker starting this page
+    marker: Span,
+    /// The first component after the page marker, if there is one
+    header: Option<PageHeaderNode>,
+    /// The entries and parts of entries on this page
+    entries: Vec<EntryNode>,
+    /// The newlines after each component of this page
+    trivia: Vec<Span>,
+}
+
+/// The header of a page, giving its entry number and recorded date ranges
+#[derive(Debug, Clone)]
+pub struct PageHeaderNode {
+    /// The whole header
+    span: Span,
+    /// The entry number range on the first line
+    numbers: RangeNode,
+    /// The recorded date range on the second line, if there is one
+    dates: Option<RangeNode>,
+    /// The time zone on the third line, if there is one
+    zone: Option<Span>,
+}
+
+/// A range of values in a page header ("1-2" or "1/1/2000-1/3/2000")
+#[derive(Debug, Clone, Copy)]
+pub struct RangeNode {
+    /// The whole range
+    span: Span,
+    /// The start of the range, or the whole range if there is no separator
+    start: Span,
+    /// The end of the range, if there is a separator
+    end: Option<Span>,
+}
+
+/// An entry, or the part of an entry continued from a previous page
+#[derive(Debug, Clone)]
+pub struct EntryNode {
+    /// The whole entry
+    span: Span,
+    /// The header line, absent if this continues an entry
+    header: Option<EntryHeaderNode>,
+    /// The lines after the header
+    lines: Vec<LineNode>,
+}
+
+/// The header line of an entry
+#[derive(Debug, Clone)]
+pub struct EntryHeaderNode {
+    /// The whole line
+    span: Span,
+    /// The words of the line, split on single spaces
+    words: Vec<Span>,
+}
+
+/// A line of an entry's body
+#[derive(Debug, Clone, Copy)]
+pub enum LineNode {
+    /// A subject line, starting a new block
+    Subject {
+        /// The whole line
+        span: Span,
+        /// The subject after its prefix
+        subject: Span,
+    },
+    /// A line of block text
+    Text {
+        /// The whole line
+        span: Span,
+        /// The continuation marker at the start of the line, if this line
+        /// continues a multi-page entry
+        continued: Option<Span>,
+        /// The text between any continuation markers
+        text: Span,
+        /// The continuation marker at the end of the line, if the entry is
+        /// continued on the next page
+        continues: Option<Span>,
+    },
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// Build the syntax tree of a logbook written in the default dialect
+    pub fn new(source: &'a str) -> Self {
+        Self::with_dialect(source, &Dialect::default())
+    }
+    /// Build the syntax tree of a logbook written in a dialect
+    pub fn with_dialect(source: &'a str, dialect: &Dialect) -> Self {
+        let components = components(source);
+        // The preamble runs up to the first page marker
+        let first_marker = components
+            .iter()
+            .position(|(component, _)| component.text == dialect.page_marker())
+            .unwrap_or(components.len());
+        let preamble_end = components
+            .get(first_marker)
+            .map_or(source.len(), |(marker, _)| marker.start);
+        let preamble = Token::new(&source[..preamble_end], 0);
+        let preamble = PreambleNode {
+            span: preamble.span(),
+            entry_range: entry_range(preamble, dialect),
+            day_start: day_start(preamble, dialect),
+        };
+        // Every component after it belongs to the page of the most recent
+        // marker, with the first one being the page header
+        let mut pages: Vec<PageNode> = Vec::new();
+        let mut continuing = false;
+        for (component, trivia) in components.into_iter().skip(first_marker) {
+            if component.text == dialect.page_marker() {
+                pages.push(PageNode {
+                    span: component.span(),
+                    marker: component.span(),
+                    header: None,
+                    entries: Vec::new(),
+                    trivia: Vec::new(),
+                });
+            } else {
+                let page = pages
+                    .last_mut()
+                    .expect("components after the first marker belong to a page");
+                if page.header.is_none() {
+                    page.header = Some(page_header(component, dialect));
+                } else {
+                    page.entries
+                        .push(entry(component, dialect, &mut continuing));
+                }
+            }
+            let page = pages
+                .last_mut()
+                .expect("components after the first marker belong to a page");
+            page.span = page.span.to(trivia);
+            if !trivia.range().is_empty() {
+                page.trivia.push(trivia);
+            }
+        }
+        Self {
+            source,
+            dialect: dialect.clone(),
+            preamble,
+            pages,
+        }
+    }
+    pub fn source(&self) -> &'a str {
+        self.source
+    }
+    pub fn dialect(&self) -> &Dialect {
+        &self.dialect
+    }
+    pub fn preamble(&self) -> &PreambleNode {
+        &self.preamble
+    }
+    pub fn pages(&self) -> &[PageNode] {
+        &self.pages
+    }
+    /// Get the source text of a span
+    pub fn text(&self, span: Span) -> &'a str {
+        &self.source[span.range()]
+    }
+    /// Apply edits to the source text, leaving everything else untouched.
+    /// Edits must not overlap
+    pub fn edit(&self, edits: &[Edit]) -> String {
+        let mut edits = edits.iter().collect::<Vec<_>>();
+        edits.sort_by_key(|edit| edit.span.start());
+        let mut edited = String::with_capacity(self.source.len());
+        let mut position = 0;
+        for edit in edits {
+            debug_assert!(edit.span.start() >= position, "edits overlap");
+            edited.push_str(&self.source[position..edit.span.start()]);
+            edited.push_str(&edit.replacement);
+            position = edit.span.end();
+        }
+        edited.push_str(&self.source[position..]);
+        edited
+    }
+}
+
+impl Edit {
+    pub fn new(span: Span, replacement: String) -> Self {
+        Self { span, replacement }
+    }
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn replacement(&self) -> &str {
+        &self.replacement
+    }
+}
+
+impl PreambleNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn entry_range(&self) -> Option<&EntryRangeNode> {
+        self.entry_range.as_ref()
+    }
+    pub fn day_start(&self) -> Option<&DayStartNode> {
+        self.day_start.as_ref()
+    }
+}
+
+impl EntryRangeNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn words(&self) -> &[Span] {
+        &self.words
+    }
+}
+
+impl DayStartNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn words(&self) -> &[Span] {
+        &self.words
+    }
+}
+
+impl PageNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn marker(&self) -> Span {
+        self.marker
+    }
+    pub fn header(&self) -> Option<&PageHeaderNode> {
+        self.header.as_ref()
+    }
+    pub fn entries(&self) -> &[EntryNode] {
+        &self.entries
+    }
+    pub fn trivia(&self) -> &[Span] {
+        &self.trivia
+    }
+}
+
+impl PageHeaderNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn numbers(&self) -> RangeNode {
+        self.numbers
+    }
+    pub fn dates(&self) -> Option<RangeNode> {
+        self.dates
+    }
+    pub fn zone(&self) -> Option<Span> {
+        self.zone
+    }
+}
+
+impl RangeNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn start(&self) -> Span {
+        self.start
+    }
+    pub fn end(&self) -> Option<Span> {
+        self.end
+    }
+}
+
+impl EntryNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn header(&self) -> Option<&EntryHeaderNode> {
+        self.header.as_ref()
+    }
+    pub fn lines(&self) -> &[LineNode] {
+        &self.lines
+    }
+    /// Check if this continues an entry from a previous page
+    pub fn is_continuation(&self) -> bool {
+        self.header.is_none()
+    }
+}
+
+impl EntryHeaderNode {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+    pub fn words(&self) -> &[Span] {
+        &self.words
+    }
+}
+
+impl LineNode {
+    /// Get the span of the whole line
+    pub fn span(&self) -> Span {
+        match self {
+            LineNode::Subject { span, .. } | LineNode::Text { span, .. } => *span,
+        }
+    }
+}
+
+/// Split text into components separated by blank lines, pairing each with the
+/// newlines that follow it. Blank lines at the very start belong to no
+/// component
+fn components(source: &str) -> Vec<(Token<'_>, Span)> {
+    let bytes = source.as_bytes();
+    let mut components = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] != b'\n' {
+            index += 1;
+            continue;
+        }
+        let newlines = bytes[index..]
+            .iter()
+            .take_while(|byte| **byte == b'\n')
+            .count();
+        let end = index + newlines;
+        // A single newline only ends a component at the end of the text
+        if newlines > 1 || end == bytes.len() {
+            if index > start {
+                components.push((
+                    Token::new(&source[start..index], start),
+                    Span::new(index, end),
+                ));
+            }
+            start = end;
+        }
+        index = end;
+    }
+    if start < bytes.len() {
+        components.push((
+            Token::new(&source[start..], start),
+            Span::new(bytes.len(), bytes.len()),
+        ));
+    }
+    components
+}
+
+/// Find the entry range of a preamble
+fn entry_range(preamble: Token, dialect: &Dialect) -> Option<EntryRangeNode> {
+    let (before, after) = preamble.split_once(dialect.entry_range_start())?;
+    let keyword = Span::new(before.end().start(), after.start);
+    let words = after
+        .split_ascii_whitespace()
+        .take(ENTRY_RANGE_WORDS)
+        .map(|word| word.span())
+        .collect::<Vec<_>>();
+    Some(EntryRangeNode {
+        span: words.last().map_or(keyword, |last| keyword.to(*last)),
+        words,
+    })
+}
+
+/// Find the declaration of when days start in a preamble
+fn day_start(preamble: Token, dialect: &Dialect) -> Option<DayStartNode> {
+    let (before, after) = preamble.split_once(dialect.day_start())?;
+    let keyword = Span::new(before.end().start(), after.start);
+    let line = after.lines().next().unwrap_or(Token::new("", after.start));
+    Some(DayStartNode {
+        span: keyword.to(line.span()),
+        words: line
+            .split_ascii_whitespace()
+            .map(|word| word.span())
+            .collect(),
+    })
+}
+
+/// Split a page header into its ranges
+fn page_header(component: Token, dialect: &Dialect) -> PageHeaderNode {
+    let (numbers, dates) = match component.split_once(PAGE_RANGE_SPLIT) {
+        Some((numbers, dates)) => (numbers, Some(dates)),
+        None => (component, None),
+    };
+    let (dates, zone) = match dates.map(|dates| dates.split_once(PAGE_RANGE_SPLIT)) {
+        Some(Some((dates, zone))) => (Some(dates), Some(zone)),
+        _ => (dates, None),
+    };
+    PageHeaderNode {
+        span: component.span(),
+        numbers: range(numbers, dialect, |number| number.parse::<u32>().is_ok()),
+        dates: dates.map(|dates| {
+            range(dates, dialect, |date| {
+                parse::read_date(date, dialect).is_ok()
+            })
+        }),
+        zone: zone.map(|zone| zone.span()),
+    }
+}
+
+/// Split a page header range at its separator. Where the separator also
+/// appears within the values ("1/1/2000-2000-01-03"), the range is split at the
+/// first occurrence where both values can be read, or failing that at its
+/// middle occurrence
+fn range(range: Token, dialect: &Dialect, reads: impl Fn(&str) -> bool) -> RangeNode {
+    let separator = dialect.page_range_separator();
+    let splits = range
+        .text
+        .match_indices(separator)
+        .map(|(index, _)| {
+            let start = range.slice(&range.text[..index]);
+            let end = range.slice(&range.text[index + separator.len()..]);
+            (start, end)
+        })
+        .collect::<Vec<_>>();
+    let split = splits
+        .iter()
+        .find(|(start, end)| reads(start.text) && reads(end.text))
+        .or(splits.get(splits.len() / 2));
+    match split {
+        Some((start, end)) => RangeNode {
+            span: range.span(),
+            start: start.span(),
+            end: Some(end.span()),
+        },
+        None => RangeNode {
+            span: range.span(),
+            start: range.span(),
+            end: None,
+        },
+    }
+}
+
+/// Split an entry into its header and lines, tracking whether the entry is
+/// being continued from a previous line
+fn entry(component: Token, dialect: &Dialect, continuing: &mut bool) -> EntryNode {
+    let mut lines = component.lines();
+    // Entries being continued have no header
+    let header = if *continuing {
+        None
+    } else {
+        lines.next().map(|line| EntryHeaderNode {
+            span: line.span(),
+            words: line.split(" ").map(|word| word.span()).collect(),
+        })
+    };
+    EntryNode {
+        span: component.span(),
+        header,
+        lines: lines
+            .map(|line| self::line(line, dialect, continuing))
+            .collect(),
+    }
+}
+
+/// Classify a line of an entry's body, finding any continuation markers
+fn line(line: Token, dialect: &Dialect, continuing: &mut bool) -> LineNode {
+    let multi_page = dialect.multi_page();
+    if let Some(subject) = line.strip_prefix(dialect.subject_prefix()) {
+        *continuing = false;
+        return LineNode::Subject {
+            span: line.span(),
+            subject: subject.span(),
+        };
+    }
+    // Only look for a leading marker if the previous line ended with one
+    let (continued, rest) = match line.strip_prefix(&format!("{multi_page} ")) {
+        Some(rest) if *continuing => (
+            Some(Span::new(line.start, line.start + multi_page.len())),
+            rest,
+        ),
+        _ => (None, line),
+    };
+    let (text, continues) = match rest
+        .strip_suffix(&format!(" {multi_page}"))
+        .or_else(|| rest.strip_suffix(multi_page))
+    {
+        Some(text) => {
+            let end = rest.end().start();
+            (text, Some(Span::new(end - multi_page.len(), end)))
+        }
+        None => (rest, None),
+    };
+    *continuing = continues.is_some();
+    LineNode::Text {
+        span: line.span(),
+        continued,
+        text: text.span(),
+        continues,
+    }
+}
+
+/// A slice of a logbook along with its position in the full source text
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Token<'a> {
+    /// The text of this token
+    pub(crate) text: &'a str,
+    /// The byte offset of this token in the source text
+    pub(crate) start: usize,
+}
+
+impl<'a> Token<'a> {
+    pub(crate) fn new(text: &'a str, start: usize) -> Self {
+        Self { text, start }
+    }
+
+    /// Create a token for a span of source text
+    pub(crate) fn of(source: &'a str, span: Span) -> Self {
+        Self::new(&source[span.range()], span.start())
+    }
+
+    /// Get the span of source text this token covers
+    pub(crate) fn span(&self) -> Span {
+        Span::new(self.start, self.start + self.text.len())
+    }
+
+    /// Get an empty span just past the end of this token
+    pub(crate) fn end(&self) -> Span {
+        Span::new(self.start + self.text.len(), self.start + self.text.len())
+    }
+
+    /// Create a token for a slice of this token's text
+    fn slice(&self, inner: &'a str) -> Token<'a> {
+        let offset = inner.as_ptr() as usize - self.text.as_ptr() as usize;
+        debug_assert!(offset + inner.len() <= self.text.len());
+        Token::new(inner, self.start + offset)
+    }
+
+    pub(crate) fn split(self, pattern: &'a str) -> impl Iterator<Item = Token<'a>> {
+        self.text.split(pattern).map(move |part| self.slice(part))
+    }
+
+    pub(crate) fn split_ascii_whitespace(self) -> impl Iterator<Item = Token<'a>> + Clone {
+        self.text
+            .split_ascii_whitespace()
+            .map(move |part| self.slice(part))
+    }
+
+    pub(crate) fn split_once(&self, pattern: &str) -> Option<(Token<'a>, Token<'a>)> {
+        self.text
+            .split_once(pattern)
+            .map(|(before, after)| (self.slice(before), self.slice(after)))
+    }
+
+    pub(crate) fn lines(self) -> impl Iterator<Item = Token<'a>> {
+        self.text.lines().map(move |line| self.slice(line))
+    }
+
+    pub(crate) fn strip_prefix(&self, prefix: &str) -> Option<Token<'a>> {
+        self.text.strip_prefix(prefix).map(|rest| self.slice(rest))
+    }
+
+    pub(crate) fn strip_suffix(&self, suffix: &str) -> Option<Token<'a>> {
+        self.text.strip_suffix(suffix).map(|rest| self.slice(rest))
+    }
+}
@@ -0,0 +1,336 @@
+use crate::dialect::Dialect;
+use crate::parse::{EntryHeader, COMPONENT_SEPARATOR, PAGE_RANGE_SPLIT};
+use crate::zone::Zone;
+use crate::{Entry, Logbook, Mark, Page, PageHeader};
+use std::fmt;
+use time::{Date, Time};
+
+/// Render a logbook in the canonical plaintext format, writing dates and times
+/// in the logbook's own style. Entries are written onto the pages they were
+/// read from, with entries split across pages at the same page breaks. Where
+/// the logbook has no pages, or they no longer match its entries, every entry
+/// is written onto a single page
+pub fn write(logbook: &Logbook) -> String {
+    logbook.to_string()
+}
+
+/// Render a logbook in the canonical plaintext format of a dialect, in the
+/// same way as [`write`]
+pub fn write_with_dialect(logbook: &Logbook, dialect: &Dialect) -> String {
+    let dialect = &dialect.styled(logbook.style());
+    // Preamble
+    let mut written = write_preamble(dialect, logbook.start(), logbook.end());
+    if logbook.next_day() != dialect.next_day() {
+        written.push('\n');
+        written.push_str(&write_day_start(dialect, logbook.next_day()));
+    }
+    match write_pages(dialect, logbook) {
+        Some(pages) => {
+            for page in pages {
+                written.push_str(COMPONENT_SEPARATOR);
+                written.push_str(dialect.page_marker());
+                for component in page {
+                    written.push_str(COMPONENT_SEPARATOR);
+                    written.push_str(&component);
+                }
+            }
+        }
+        None => {
+            // Single page containing every entry
+            written.push_str(COMPONENT_SEPARATOR);
+            written.push_str(dialect.page_marker());
+            if let (Some(first), Some(last)) = (logbook.entries().first(), logbook.entries().last())
+            {
+                written.push_str(COMPONENT_SEPARATOR);
+                written.push_str(&write_page_header(dialect, first, last));
+                for entry in logbook {
+                    written.push_str(COMPONENT_SEPARATOR);
+                    written.push_str(&write_entry(dialect, entry));
+                }
+            }
+        }
+    }
+    written.push('\n');
+    written
+}
+
+/// Render the header and the entries or parts of entries of each page of a
+/// logbook, or None if its pages don't match its entries or an entry can't be
+/// split into as many parts as it has pages
+fn write_pages(dialect: &Dialect, logbook: &Logbook) -> Option<Vec<Vec<String>>> {
+    if logbook.pages().is_empty() {
+        return None;
+    }
+    // Find the entry each part belongs to, checking that the parts of each
+    // entry follow on from each other and that every entry has its parts.
+    // Entries are written with the time zone of the page their header is on
+    let mut parts = vec![0; logbook.len()];
+    let mut zones = vec![None; logbook.len()];
+    let mut layout = Vec::new();
+    let mut index = None;
+    let mut continues = false;
+    for page in logbook.pages() {
+        let mut entries = Vec::new();
+        for part in page.entries() {
+            if part.continued() != continues {
+                return None;
+            }
+            if !part.continued() {
+                index = Some(index.map_or(0, |index| index + 1));
+            }
+            let index = index?;
+            if logbook.entries().get(index)?.position().entry_number() != part.entry_number() {
+                return None;
+            }
+            parts[index] += 1;
+            if !part.continued() {
+                zones[index] = page.header().and_then(PageHeader::zone);
+            }
+            entries.push(index);
+            continues = part.continues();
+        }
+        layout.push(entries);
+    }
+    if continues || index.map_or(0, |index| index + 1) != logbook.len() {
+        return None;
+    }
+    let mut split = logbook
+        .iter()
+        .zip(parts.into_iter().zip(zones))
+        .map(|(entry, (parts, zone))| {
+            write_entry_parts(dialect, entry, parts, zone).map(|parts| parts.into_iter())
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(
+        logbook
+            .pages()
+            .iter()
+            .zip(layout)
+            .map(|(page, entries)| {
+                let header = write_recorded_page_header(dialect, logbook, page);
+                header
+                    .into_iter()
+                    .chain(entries.into_iter().filter_map(|index| split[index].next()))
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+/// Render the header of a page as it was read, or as it would be computed
+/// from the entries on the page if it couldn't be read
+fn write_recorded_page_header(dialect: &Dialect, logbook: &Logbook, page: &Page) -> Option<String> {
+    let Some(header) = page.header() else {
+        let first = logbook.entry(page.entries().first()?.entry_number())?;
+        let last = logbook.entry(page.entries().last()?.entry_number())?;
+        return Some(write_page_header(dialect, first, last));
+    };
+    let mut written = write_page_range(
+        dialect,
+        (header.first_number(), header.first_date()),
+        (header.last_number(), header.last_date()),
+    );
+    if let Some(zone) = header.zone() {
+        written.push_str(PAGE_RANGE_SPLIT);
+        written.push_str(&zone.to_string());
+    }
+    Some(written)
+}
+
+impl fmt::Display for Logbook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&write_with_dialect(self, &Dialect::default()))
+    }
+}
+
+/// Render the entry range of a logbook's preamble
+pub fn write_preamble(dialect: &Dialect, start: &Mark, end: Option<&Mark>) -> String {
+    let end = match end {
+        Some(end) => write_mark(dialect, end),
+        None => format!(
+            "{placeholder} {} {placeholder}",
+            dialect.entry_range_mark_separator(),
+            placeholder = dialect.entry_range_placeholder(),
+        ),
+    };
+    format!(
+        "{} {} {} {end}",
+        dialect.entry_range_start(),
+        write_mark(dialect, start),
+        dialect.entry_range_separator(),
+    )
+}
+
+/// Render the declaration of the hour before which entries count for the
+/// previous day
+pub fn write_day_start(dialect: &Dialect, next_day: u8) -> String {
+    let time = Time::from_hms(next_day, 0, 0).expect("next-day hours are valid hours");
+    format!("{} {}", dialect.day_start(), write_time(dialect, time))
+}
+
+/// Render the header of a page running from one entry to another
+pub fn write_page_header(dialect: &Dialect, first: &Entry, last: &Entry) -> String {
+    write_page_range(
+        dialect,
+        (first.position().entry_number(), first.recorded_date()),
+        (last.position().entry_number(), last.recorded_date()),
+    )
+}
+
+/// Render a page header from the entry numbers and recorded dates of the
+/// first and last entries on the page
+pub(crate) fn write_page_range(dialect: &Dialect, first: (u32, Date), last: (u32, Date)) -> String {
+    format!(
+        "{}{PAGE_RANGE_SPLIT}{}{}{}",
+        write_number_range(dialect, first.0, last.0),
+        write_date(dialect, first.1),
+        dialect.page_range_separator(),
+        write_date(dialect, last.1),
+    )
+}
+
+/// Render the entry number range on the first line of a page header
+pub(crate) fn write_number_range(dialect: &Dialect, first: u32, last: u32) -> String {
+    format!("{first}{}{last}", dialect.page_range_separator())
+}
+
+/// Render an entry, including its header and every block
+pub fn write_entry(dialect: &Dialect, entry: &Entry) -> String {
+    write_entry_parts(dialect, entry, 1, None)
+        .and_then(|parts| parts.into_iter().next())
+        .expect("entries can always be written in one part")
+}
+
+/// Render an entry split into parts for consecutive pages, marking each page
+/// break with continuation markers, or None if its text has too few places to
+/// split at. Text is split at single spaces, which reading the entry joins its
+/// lines with, so the split entry reads back the same
+fn write_entry_parts(
+    dialect: &Dialect,
+    entry: &Entry,
+    parts: usize,
+    page_zone: Option<Zone>,
+) -> Option<Vec<String>> {
+    let multi_page = dialect.multi_page();
+    // Spread the page breaks evenly over the places the text can be split
+    let places = entry
+        .contents()
+        .iter()
+        .enumerate()
+        .flat_map(|(index, block)| {
+            let text = block.text();
+            text.match_indices(' ')
+                .map(|(offset, _)| offset)
+                .filter(|offset| {
+                    let before = text[..*offset].chars().next_back();
+                    let after = text[offset + 1..].chars().next();
+                    before.is_some_and(|before| !before.is_whitespace())
+                        && after.is_some_and(|after| !after.is_whitespace())
+                })
+                .map(move |offset| (index, offset))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let breaks = parts.checked_sub(1)?;
+    if places.len() < breaks {
+        return None;
+    }
+    let breaks = (1..=breaks)
+        .map(|part| places[part * places.len() / parts])
+        .collect::<Vec<_>>();
+    let mut written = vec![write_entry_header_in(dialect, entry, page_zone)];
+    for (index, block) in entry.contents().iter().enumerate() {
+        let current = written.last_mut()?;
+        current.push('\n');
+        current.push_str(&write_subject_line(dialect, block.subject()));
+        let text = block.text();
+        if text.is_empty() {
+            continue;
+        }
+        current.push('\n');
+        let mut start = 0;
+        for (_, offset) in breaks.iter().filter(|(block, _)| *block == index) {
+            let current = written.last_mut()?;
+            current.push_str(&text[start..*offset]);
+            current.push(' ');
+            current.push_str(multi_page);
+            written.push(format!("{multi_page} "));
+            start = offset + 1;
+        }
+        written.last_mut()?.push_str(&text[start..]);
+    }
+    Some(written)
+}
+
+/// Render the line starting a block with a subject
+pub(crate) fn write_subject_line(dialect: &Dialect, subject: &str) -> String {
+    format!("{}{subject}", dialect.subject_prefix())
+}
+
+/// Render the header line of an entry. The finished time is only annotated
+/// with a time zone if it differs from the one the entry was started in
+pub fn write_entry_header(dialect: &Dialect, entry: &Entry) -> String {
+    write_entry_header_in(dialect, entry, None)
+}
+
+/// Render the header line of an entry on a page with a time zone, leaving out
+/// the started time's zone if it is the page's
+fn write_entry_header_in(dialect: &Dialect, entry: &Entry, page_zone: Option<Zone>) -> String {
+    write_header_line(
+        dialect,
+        &EntryHeader {
+            number: entry.position().entry_number(),
+            date: entry.recorded_date(),
+            started: entry.started().time(),
+            finished: entry.finished().time(),
+            started_zone: entry.started_zone().filter(|zone| Some(*zone) != page_zone),
+            finished_zone: entry
+                .finished_zone()
+                .filter(|zone| Some(*zone) != entry.started_zone()),
+        },
+    )
+}
+
+/// Render an entry header line from its values
+pub(crate) fn write_header_line(dialect: &Dialect, header: &EntryHeader) -> String {
+    format!(
+        "{} {}{} {} {} {}{} {} {}{}",
+        dialect.header_start(),
+        header.number,
+        dialect.header_post_number(),
+        write_date(dialect, header.date),
+        dialect.header_started(),
+        write_time(dialect, header.started),
+        write_zone(header.started_zone),
+        dialect.header_finished(),
+        write_time(dialect, header.finished),
+        write_zone(header.finished_zone),
+    )
+}
+
+/// Render the time zone annotation following a time, if there is one
+fn write_zone(zone: Option<Zone>) -> String {
+    zone.map(|zone| format!(" {zone}")).unwrap_or_default()
+}
+
+/// Render a mark as it appears in a preamble
+fn write_mark(dialect: &Dialect, mark: &Mark) -> String {
+    format!(
+        "{} {} {}",
+        write_date(dialect, mark.effective_date()),
+        dialect.entry_range_mark_separator(),
+        mark.entry_number()
+    )
+}
+
+/// Render a date in a dialect's format
+pub fn write_date(dialect: &Dialect, date: Date) -> String {
+    date.format(dialect.date_format())
+        .expect("dialect date formats are checked when set")
+}
+
+/// Render a time in a dialect's format
+pub fn write_time(dialect: &Dialect, time: Time) -> String {
+    time.format(dialect.time_format())
+        .expect("dialect time formats are checked when set")
+}
@@ -0,0 +1,142 @@
+use std::fmt;
+use time::{Duration, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+use time_tz::{timezones, Offset, OffsetResult, TimeZone, Tz};
+
+/// Start of a fixed offset from UTC
+const UTC: &str = "UTC";
+
+/// Largest number of hours a fixed zone may be offset ahead of UTC
+pub(crate) const MAX_OFFSET_HOURS: i8 = 14;
+
+/// Largest number of hours a fixed zone may be offset behind UTC
+const MIN_OFFSET_HOURS: i8 = -12;
+
+/// A time zone that timestamps may be annotated with
+#[derive(Clone, Copy, PartialEq)]
+pub enum Zone {
+    /// A fixed offset from UTC ("UTC", "UTC+2", "UTC-5:30")
+    Fixed(UtcOffset),
+    /// A zone from the bundled tz database ("Europe/Berlin"), following its
+    /// daylight saving time changes
+    Named(&'static Tz),
+}
+
+impl Zone {
+    /// Read a zone annotation, returning None if the text isn't one
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.strip_prefix(UTC) {
+            Some(offset) => parse_offset(offset).map(Zone::Fixed),
+            None => timezones::get_by_name(text).map(Zone::Named),
+        }
+    }
+
+    /// Check if some text looks like it was meant to be a zone annotation
+    pub(crate) fn resembles(text: &str) -> bool {
+        text.starts_with(UTC) || text.contains('/')
+    }
+
+    /// Get the offset from UTC of a local time in this zone. Local times that
+    /// happen twice when clocks go back take the earlier offset, and local
+    /// times skipped when clocks go forward take the offset after the change
+    pub fn offset_at(&self, local: PrimitiveDateTime) -> UtcOffset {
+        let tz = match self {
+            Zone::Fixed(offset) => return *offset,
+            Zone::Named(tz) => tz,
+        };
+        let local = local.assume_utc();
+        match tz.get_offset_local(&local) {
+            OffsetResult::Some(offset) | OffsetResult::Ambiguous(offset, _) => offset.to_utc(),
+            OffsetResult::None => {
+                let before = tz.get_offset_utc(&(local - Duration::DAY)).to_utc();
+                let instant = local - Duration::seconds(before.whole_seconds().into());
+                tz.get_offset_utc(&instant).to_utc()
+            }
+        }
+    }
+
+    /// Attach this zone's offset to a local time in it
+    pub fn assume(&self, local: PrimitiveDateTime) -> OffsetDateTime {
+        local.assume_offset(self.offset_at(local))
+    }
+}
+
+impl fmt::Debug for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Named zones are shown by name rather than their every transition
+        match self {
+            Zone::Fixed(offset) => f.debug_tuple("Fixed").field(offset).finish(),
+            Zone::Named(tz) => f.debug_tuple("Named").field(&tz.name()).finish(),
+        }
+    }
+}
+
+impl fmt::Display for Zone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Zone::Fixed(offset) => {
+                f.write_str(UTC)?;
+                let (hours, minutes, _) = offset.as_hms();
+                if offset.is_utc() {
+                    Ok(())
+                } else if minutes == 0 {
+                    write!(f, "{hours:+}")
+                } else {
+                    let sign = if offset.is_negative() { '-' } else { '+' };
+                    write!(f, "{sign}{}:{:02}", hours.abs(), minutes.abs())
+                }
+            }
+            Zone::Named(tz) => f.write_str(tz.name()),
+        }
+    }
+}
+
+/// Attach a zone's offset to a local time, taking times without a zone to be
+/// in UTC
+pub(crate) fn assume(local: PrimitiveDateTime, zone: Option<Zone>) -> OffsetDateTime {
+    match zone {
+        Some(zone) => zone.assume(local),
+        None => local.assume_utc(),
+    }
+}
+
+/// Read the offset after "UTC" in a fixed zone ("", "+2", "-05:30"). Offsets
+/// beyond those in use anywhere (UTC-12 to UTC+14) are rejected
+fn parse_offset(offset: &str) -> Option<UtcOffset> {
+    if offset.is_empty() {
+        return Some(UtcOffset::UTC);
+    }
+    let (sign, offset) = match (offset.strip_prefix('+'), offset.strip_prefix('-')) {
+        (Some(offset), _) => (1, offset),
+        (_, Some(offset)) => (-1, offset),
+        _ => return None,
+    };
+    let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+    let digits = |text: &str| text.bytes().all(|byte| byte.is_ascii_digit());
+    if hours.is_empty() || !digits(hours) || !digits(minutes) {
+        return None;
+    }
+    let hours = sign * hours.parse::<i8>().ok()?;
+    let minutes = sign * minutes.parse::<i8>().ok().filter(|minutes| *minutes < 60)?;
+    // The bounds are whole hours, so only offsets within them may have minutes
+    let bounds = MIN_OFFSET_HOURS..=MAX_OFFSET_HOURS;
+    if !bounds.contains(&hours) || (minutes != 0 && !bounds.contains(&(hours + minutes.signum()))) {
+        return None;
+    }
+    UtcOffset::from_hms(hours, minutes, 0).ok()
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Zone {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Zone {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Zone::parse(&name)
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown time zone {name}")))
+    }
+}
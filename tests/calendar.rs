@@ -0,0 +1,85 @@
+use logbook_integrity::calendar::Calendar;
+use logbook_integrity::{Block, Entry, Logbook, Mark};
+use time::macros::date;
+use time::{Date, Duration};
+
+/// Create a logbook from 1/1/2000 to 1/6/2000 with an entry of some words on
+/// each day but 1/5/2000
+fn logbook() -> Logbook {
+    let entries = (1..)
+        .zip([1, 2, 3, 4, 0, 8])
+        .filter(|(_, words)| *words > 0)
+        .map(|(number, words)| {
+            let date = date!(2000 - 01 - 01) + Duration::days(i64::from(number) - 1);
+            let started = date.with_hms(20, 0, 0).unwrap();
+            Entry::new(
+                Mark::new(date, number),
+                started,
+                started + Duration::minutes(10),
+                vec![Block::new("A".to_owned(), "word ".repeat(words))],
+            )
+        })
+        .collect::<Vec<_>>();
+    Logbook::new(
+        Mark::new(date!(2000 - 01 - 01), 1),
+        Some(Mark::new(date!(2000 - 01 - 06), 6)),
+        entries,
+    )
+}
+
+#[test]
+fn days_without_entries_are_missing() {
+    let calendar = Calendar::new(&[logbook()]);
+    assert_eq!(calendar.words_on(date!(2000 - 01 - 06)), Some(8));
+    assert_eq!(calendar.words_on(date!(2000 - 01 - 05)), None);
+    assert_eq!(
+        calendar.missing().iter().copied().collect::<Vec<Date>>(),
+        [date!(2000 - 01 - 05)]
+    );
+    // Days before or after a logbook aren't missing
+    assert!(!calendar.is_missing(date!(1999 - 12 - 31)));
+    assert!(!calendar.is_missing(date!(2000 - 01 - 07)));
+    assert_eq!(calendar.years().into_iter().collect::<Vec<_>>(), [2000]);
+}
+
+#[test]
+fn open_logbooks_are_covered_to_their_last_entry() {
+    let mut logbook = logbook();
+    logbook = Logbook::new(logbook.start().clone(), None, logbook.entries().to_vec());
+    let calendar = Calendar::new(&[logbook]);
+    assert!(calendar.is_missing(date!(2000 - 01 - 05)));
+    assert!(!calendar.is_missing(date!(2000 - 01 - 07)));
+}
+
+#[test]
+fn days_are_shaded_by_quartile() {
+    // 2000 starts on a Saturday, so 1/1/2000 is in the first column and the
+    // rest of the logbook in the second
+    let rendered = Calendar::new(&[logbook()]).render(2000, false);
+    let lines = rendered.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "2000");
+    assert!(lines[1].starts_with("    Jan"));
+    assert_eq!(
+        lines[2..9],
+        ["     ░", "Mon  ▒", "     ▓", "Wed  ×", "     █", "Fri ", "    ░",]
+    );
+    assert_eq!(lines[9], "    Less ░▒▓█ More   × missing");
+    assert_eq!(lines[10], "    5 day(s) written, 1 day(s) missing");
+    assert!(!rendered.contains('\x1b'));
+}
+
+#[test]
+fn colored_calendars_are_styled() {
+    let rendered = Calendar::new(&[logbook()]).render(2000, true);
+    assert!(rendered.starts_with("\x1b[1m2000\x1b[0m\n"));
+    assert!(rendered.contains("\x1b[1;31m×\x1b[0m"));
+    assert!(rendered.contains("\x1b[38;5;40m█\x1b[0m"));
+}
+
+#[test]
+fn years_without_activity_are_empty() {
+    let rendered = Calendar::new(&[logbook()]).render(2001, false);
+    let grid = rendered.lines().skip(2).take(7).collect::<String>();
+    assert!(!grid.contains(['░', '▒', '▓', '█', '×']));
+    assert!(rendered.ends_with("0 day(s) written, 0 day(s) missing\n"));
+}
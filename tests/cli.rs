@@ -0,0 +1,286 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// A logbook with one entry
+const LOGBOOK: &str = "\
+Entries from 1/1/2000 - 1 to 1/1/2000 - 1
+
+-----
+
+1-1
+1/1/2000-1/1/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    Entries/Format
+Each entry starts with a header.
+";
+
+/// Write a logbook to a file for the program to read
+fn logbook(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    fs::write(&path, LOGBOOK).unwrap();
+    path
+}
+
+/// Create an empty directory for a test to fill
+fn directory(name: &str) -> PathBuf {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if path.exists() {
+        fs::remove_dir_all(&path).unwrap();
+    }
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+/// Fill a directory with logbooks, some of which are hidden, nested, or not
+/// named like logbooks
+fn fill(directory: &Path) {
+    for file in [
+        "b.txt",
+        "a.txt",
+        "skip.txt",
+        "notes.md",
+        "sub/c.txt",
+        ".hidden/d.txt",
+    ] {
+        let path = directory.join(file);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, LOGBOOK).unwrap();
+    }
+}
+
+/// Validate some paths, giving the files that were read relative to a
+/// directory, in order
+fn validated(directory: &Path, args: &[&str]) -> Vec<String> {
+    let output = run(&[&["validate"], args].concat());
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .filter_map(|line| line.strip_suffix(": 0 error(s), 0 warning(s)"))
+        .map(|path| {
+            Path::new(path)
+                .strip_prefix(directory)
+                .unwrap()
+                .display()
+                .to_string()
+        })
+        .collect()
+}
+
+/// Run the program with some arguments
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_logbook-integrity"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn search_takes_options_before_the_query() {
+    let path = logbook("search-options.txt");
+    let output = run(&["search", "--ignore-case", "ENTRY", path.to_str().unwrap()]);
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(
+        stdout.starts_with("2000-01-01 #1 Entries/Format\n"),
+        "{stdout}"
+    );
+}
+
+#[test]
+fn search_takes_options_after_the_query() {
+    let path = logbook("search-options-after.txt");
+    let output = run(&["search", "ENTRY", path.to_str().unwrap(), "--ignore-case"]);
+    assert!(output.status.success(), "{output:?}");
+}
+
+#[test]
+fn search_needs_a_query() {
+    let output = run(&["search", "--ignore-case"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn search_rejects_bad_regular_expressions() {
+    let path = logbook("search-regex.txt");
+    let output = run(&["search", "--regex", "(entry", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn search_filters_by_subject_and_date() {
+    let path = logbook("search-filters.txt");
+    let path = path.to_str().unwrap();
+    let hits = |args: &[&str]| {
+        let output = run(&[&["search", "entry"], args, &[path]].concat());
+        assert!(output.status.success(), "{output:?}");
+        !String::from_utf8(output.stdout)
+            .unwrap()
+            .starts_with("No blocks match")
+    };
+    assert!(hits(&[
+        "--subject=Entries",
+        "--from=1/1/2000",
+        "--to=1/1/2000"
+    ]));
+    assert!(!hits(&["--subject=Pages"]));
+    assert!(!hits(&["--from=1/2/2000"]));
+    assert!(!hits(&["--to=12/31/1999"]));
+    assert_eq!(
+        run(&["search", "entry", "--from=tomorrow", path])
+            .status
+            .code(),
+        Some(4)
+    );
+}
+
+#[test]
+fn validate_only_checks_volumes_when_asked() {
+    let first = logbook("volumes-first.txt");
+    let second = logbook("volumes-second.txt");
+    let paths = [first.to_str().unwrap(), second.to_str().unwrap()];
+    let output = run(&["validate", paths[0], paths[1]]);
+    assert!(output.status.success(), "{output:?}");
+    let output = run(&["validate", "--volumes", "--color=never", paths[0], paths[1]]);
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("error[volume-overlap]"), "{stdout}");
+    // Pointing at the entry range of the second volume
+    assert!(stdout.contains("volumes-second.txt:1:1"), "{stdout}");
+}
+
+#[test]
+fn directories_are_read_in_order_skipping_hidden_directories() {
+    let directory = directory("expand-order");
+    fill(&directory);
+    let root = directory.to_str().unwrap();
+    assert_eq!(
+        validated(&directory, &[root]),
+        ["a.txt", "b.txt", "skip.txt", "sub/c.txt"]
+    );
+}
+
+#[test]
+fn files_are_only_read_once() {
+    let directory = directory("expand-once");
+    fill(&directory);
+    let root = directory.to_str().unwrap();
+    let file = directory.join("sub/../b.txt");
+    let file = file.to_str().unwrap();
+    assert_eq!(
+        validated(&directory, &[file, root]),
+        ["sub/../b.txt", "a.txt", "skip.txt", "sub/c.txt"]
+    );
+}
+
+#[test]
+fn directories_are_read_with_include_and_exclude_patterns() {
+    let directory = directory("expand-patterns");
+    fill(&directory);
+    let root = directory.to_str().unwrap();
+    assert_eq!(
+        validated(&directory, &["--exclude=skip*", root]),
+        ["a.txt", "b.txt", "sub/c.txt"]
+    );
+    assert_eq!(
+        validated(&directory, &["--include=*.md", "--include=a.*", root]),
+        ["a.txt", "notes.md"]
+    );
+    // Files given directly are read whatever their names
+    let notes = directory.join("notes.md");
+    assert_eq!(
+        validated(&directory, &["--exclude=*.md", notes.to_str().unwrap()]),
+        ["notes.md"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn symlink_loops_end() {
+    let directory = directory("expand-loop");
+    fill(&directory);
+    std::os::unix::fs::symlink(&directory, directory.join("sub/loop")).unwrap();
+    let root = directory.to_str().unwrap();
+    assert_eq!(
+        validated(&directory, &[root]),
+        ["a.txt", "b.txt", "skip.txt", "sub/c.txt"]
+    );
+}
+
+#[test]
+fn next_day_option_sets_when_days_start() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("next-day.txt");
+    let late = LOGBOOK.replace("8:00 PM finished 8:10 PM", "11:00 AM finished 11:10 AM");
+    fs::write(&path, late).unwrap();
+    let path = path.to_str().unwrap();
+    // Without it, the entry counts for the day it was written on
+    assert_eq!(run(&["validate", path]).status.code(), Some(0));
+    // With it, the entry counts for the day before the logbook starts
+    assert_eq!(
+        run(&["validate", "--next-day=12", path]).status.code(),
+        Some(2)
+    );
+}
+
+#[test]
+fn diagnostics_are_output_as_json_lines() {
+    let path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("jsonl.txt");
+    // The first page header ends on an entry on the next page
+    let broken = LOGBOOK
+        .replace("1/1/2000 - 1\n", "1/2/2000 - 2\n")
+        .replace("1-1\n", "1-2\n")
+        + "\n-----\n\n2-2\n1/2/2000-1/2/2000\n\n\
+           Entry 2: 1/2/2000 started 8:00 PM finished 8:10 PM\n    B\ntext\n";
+    fs::write(&path, broken).unwrap();
+    let path = path.to_str().unwrap();
+    let output = run(&["validate", "--format=jsonl", path]);
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "{stdout}");
+    for line in &lines {
+        assert!(line.starts_with("{\"code\":\""), "{line}");
+        assert!(line.ends_with('}'), "{line}");
+    }
+    assert!(
+        lines
+            .iter()
+            .any(|line| line.contains(r#""labels":[{"start":"#)),
+        "{stdout}"
+    );
+    // The JSON format has the same objects in an array
+    let output = run(&["validate", "--format=json", path]);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, format!("[{}]\n", lines.join(",")));
+}
+
+#[test]
+fn calendar_shows_each_year_or_the_chosen_year() {
+    let path = logbook("calendar.txt");
+    let path = path.to_str().unwrap();
+    let stdout = |args: &[&str]| {
+        let output = run(&[&["calendar"], args, &[path]].concat());
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let every = stdout(&["--color=never"]);
+    assert!(every.starts_with("2000\n"), "{every}");
+    assert!(
+        every.contains("1 day(s) written, 0 day(s) missing"),
+        "{every}"
+    );
+    assert!(!every.contains('\x1b'), "{every}");
+    let chosen = stdout(&["--color=never", "--year=2001"]);
+    assert!(chosen.starts_with("2001\n"), "{chosen}");
+    assert!(
+        chosen.contains("0 day(s) written, 0 day(s) missing"),
+        "{chosen}"
+    );
+    assert!(stdout(&["--color=always"]).contains('\x1b'));
+    assert_eq!(
+        run(&["calendar", "--year=next", path]).status.code(),
+        Some(4)
+    );
+}
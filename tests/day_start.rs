@@ -0,0 +1,116 @@
+use logbook_integrity::dialect::Dialect;
+use logbook_integrity::{parse, write, Logbook};
+use time::macros::date;
+use time::Date;
+
+/// A logbook with entries just before and at the default next-day hour
+const DEFAULT: &str = "\
+Entries from 1/1/2000 - 1 to 1/3/2000 - 3
+
+-----
+
+1-3
+1/1/2000-1/3/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/3/2000 started 5:59 AM finished 6:10 AM
+    B
+text
+
+Entry 3: 1/3/2000 started 6:00 PM finished 6:10 PM
+    C
+text
+";
+
+/// A logbook whose days start at noon
+const NOON: &str = "\
+Entries from 1/1/2000 - 1 to 1/3/2000 - 3
+Days start at 12:00 PM
+
+-----
+
+1-3
+1/1/2000-1/3/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/3/2000 started 11:59 AM finished 12:10 PM
+    B
+text
+
+Entry 3: 1/3/2000 started 12:00 PM finished 12:10 PM
+    C
+text
+";
+
+/// Parse a logbook in a dialect, checking that it has no problems
+fn parsed(logbook: &str, dialect: &Dialect) -> Logbook {
+    let (logbook, diagnostics) = parse::parse_with_dialect(logbook, dialect)
+        .unwrap()
+        .into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    logbook
+}
+
+/// Get the effective dates of the entries of a logbook
+fn effective_dates(logbook: &Logbook) -> Vec<Date> {
+    logbook
+        .iter()
+        .map(|entry| entry.position().effective_date())
+        .collect()
+}
+
+#[test]
+fn entries_before_6_am_count_for_the_previous_day() {
+    let logbook = parsed(DEFAULT, &Dialect::default());
+    assert_eq!(logbook.next_day(), 6);
+    assert_eq!(
+        effective_dates(&logbook),
+        [
+            date!(2000 - 01 - 01),
+            date!(2000 - 01 - 02),
+            date!(2000 - 01 - 03)
+        ]
+    );
+    assert_eq!(
+        logbook.effective_date(date!(2000 - 01 - 03).with_hms(6, 0, 0).unwrap()),
+        date!(2000 - 01 - 03)
+    );
+    assert_eq!(write::write(&logbook), DEFAULT);
+}
+
+#[test]
+fn preamble_declares_when_days_start() {
+    let logbook = parsed(NOON, &Dialect::default());
+    assert_eq!(logbook.next_day(), 12);
+    assert_eq!(
+        effective_dates(&logbook),
+        [
+            date!(2000 - 01 - 01),
+            date!(2000 - 01 - 02),
+            date!(2000 - 01 - 03)
+        ]
+    );
+    let written = write::write(&logbook);
+    assert_eq!(written, NOON);
+    assert_eq!(parsed(&written, &Dialect::default()), logbook);
+}
+
+#[test]
+fn dialect_sets_when_days_start_unless_the_logbook_does() {
+    let mut dialect = Dialect::default();
+    dialect.set("next_day", "12").unwrap();
+    let undeclared = NOON.replace("Days start at 12:00 PM\n", "");
+    let logbook = parsed(&undeclared, &dialect);
+    assert_eq!(logbook.next_day(), 12);
+    // The logbook is written for the dialect, so the hour needn't be declared
+    assert_eq!(write::write_with_dialect(&logbook, &dialect), undeclared);
+    // A logbook's own declaration wins
+    dialect.set("next_day", "3").unwrap();
+    assert_eq!(parsed(NOON, &dialect).next_day(), 12);
+}
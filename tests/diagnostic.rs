@@ -0,0 +1,31 @@
+use logbook_integrity::diagnostic::{Code, Diagnostic, Span};
+use logbook_integrity::Mark;
+use time::macros::date;
+
+#[test]
+fn json_escapes_strings() {
+    let diagnostic = Diagnostic::error(
+        Code::BadDate,
+        "quote \" backslash \\ newline \n tab \t bell \u{7} é",
+    );
+    assert_eq!(
+        diagnostic.to_json(),
+        r#"{"code":"bad-date","severity":"error","message":"quote \" backslash \\ newline \n tab \t bell \u0007 é","file":null,"line":null,"column":null,"start":null,"end":null,"mark":null,"labels":[],"notes":[]}"#
+    );
+}
+
+#[test]
+fn json_includes_every_detail() {
+    let source = "first line\nsecond \"line\"\n";
+    let diagnostic = Diagnostic::warning(Code::LongEntry, "entry took too long")
+        .with_file("dir/log\\book.txt")
+        .with_span(Span::new(11, 17))
+        .with_mark(Mark::new(date!(2000 - 01 - 02), 2))
+        .with_label(Span::new(0, 5), "previous \"entry\"")
+        .with_note("note\r\none")
+        .locate(source);
+    assert_eq!(
+        diagnostic.to_json(),
+        r#"{"code":"long-entry","severity":"warning","message":"entry took too long","file":"dir/log\\book.txt","line":2,"column":1,"start":11,"end":17,"mark":{"effective_date":"2000-01-02","entry_number":2},"labels":[{"start":0,"end":5,"message":"previous \"entry\""}],"notes":["note\r\none"]}"#
+    );
+}
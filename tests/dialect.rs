@@ -0,0 +1,94 @@
+use logbook_integrity::diagnostic::Code;
+use logbook_integrity::dialect::Dialect;
+use logbook_integrity::{parse, write};
+
+/// A logbook written in the German dialect of the README
+const GERMAN: &str = "\
+Einträge vom 17.10.2026 - 5 bis 18.10.2026 - 6
+
+-----
+
+5-6
+17.10.2026-18.10.2026
+
+Eintrag 5: 17.10.2026 begonnen 20:00 beendet 20:30
+\tArbeit
+Text
+
+Eintrag 6: 18.10.2026 begonnen 20:00 beendet 20:30
+\tArbeit
+Mehr Text
+";
+
+/// Get the dialect file given as an example in the README
+fn readme_dialect() -> String {
+    let readme = include_str!("../README.md");
+    let (_, example) = readme.split_once("can be read with:\n\n```\n").unwrap();
+    let (example, _) = example.split_once("```").unwrap();
+    example.to_owned()
+}
+
+/// Read a dialect file expected to be bad, giving the error's message and line
+fn bad(config: &str) -> (String, usize) {
+    let error = Dialect::parse(config).unwrap_err();
+    assert_eq!(error.code(), Code::BadConfig);
+    (error.message().to_owned(), error.location().unwrap().line())
+}
+
+#[test]
+fn dialect_values_may_be_quoted() {
+    let dialect = Dialect::parse(
+        "header_start =   Eintrag  \n\
+         subject_prefix = \"  \"\n\
+         multi_page = \"(\\\"->\\\\)\"\n\
+         day_start = \"Tage\\tbeginnen\\num\"\n",
+    )
+    .unwrap();
+    assert_eq!(dialect.header_start(), "Eintrag");
+    assert_eq!(dialect.subject_prefix(), "  ");
+    assert_eq!(dialect.multi_page(), "(\"->\\)");
+    assert_eq!(dialect.day_start(), "Tage\tbeginnen\num");
+}
+
+#[test]
+fn dialect_ignores_comments_and_blank_lines() {
+    let dialect =
+        Dialect::parse("# header_start = Eintrag\n\n   \n  # indented\nnext_day = 4\n").unwrap();
+    assert_eq!(dialect.header_start(), Dialect::default().header_start());
+    assert_eq!(dialect.next_day(), 4);
+}
+
+#[test]
+fn dialect_rejects_bad_settings() {
+    assert_eq!(
+        bad("# comment\nheader_colour = red\n"),
+        ("unknown setting header_colour".to_owned(), 2)
+    );
+    assert_eq!(bad("header_start\n").1, 1);
+    assert_eq!(bad("subject_prefix = \"\\q\"\n").0, "unknown escape \\q");
+    assert_eq!(bad("subject_prefix = \"  \n").0, "no closing quote");
+    assert_eq!(bad("subject_prefix = \"\\\"\n").0, "unfinished escape");
+    assert_eq!(
+        bad("header_start = two words\n").0,
+        "header_start must be a single word"
+    );
+    assert_eq!(bad("next_day = 24\n").1, 1);
+    assert!(bad("\ndate_format = [mnth]/[day]\n")
+        .0
+        .starts_with("bad date_format"));
+    assert_eq!(
+        bad("date_format = [day] [month]\n").0,
+        "date_format must write dates without spaces"
+    );
+}
+
+#[test]
+fn readme_german_dialect_round_trips() {
+    let dialect = Dialect::parse(&readme_dialect()).unwrap();
+    let (logbook, diagnostics) = parse::parse_with_dialect(GERMAN, &dialect)
+        .unwrap()
+        .into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    assert_eq!(logbook.len(), 2);
+    assert_eq!(write::write_with_dialect(&logbook, &dialect), GERMAN);
+}
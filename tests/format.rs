@@ -0,0 +1,82 @@
+use logbook_integrity::diagnostic::Code;
+use logbook_integrity::dialect::Dialect;
+use logbook_integrity::{format, parse, Logbook};
+
+/// A logbook with a line of only whitespace between two paragraphs of an entry
+const WHITESPACE_LINE: &str = "\
+Entries from 1/1/2000 - 1 to 1/2/2000 - 2
+
+-----
+
+1-2
+1/1/2000-1/2/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+first paragraph
+ 
+second paragraph
+
+Entry 2: 1/2/2000 started 8:00 PM finished 8:10 PM
+    B
+text
+";
+
+/// Check that two logbooks have the same entries, other than the whitespace
+/// in their text
+fn assert_same_entries(before: &Logbook, after: &Logbook) {
+    let words = |logbook: &Logbook| {
+        logbook
+            .iter()
+            .map(|entry| {
+                let text = entry
+                    .contents()
+                    .iter()
+                    .map(|block| {
+                        block
+                            .text()
+                            .split_whitespace()
+                            .map(str::to_owned)
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+                (
+                    entry.position().clone(),
+                    entry.started(),
+                    entry.finished(),
+                    text,
+                )
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(words(before), words(after));
+}
+
+#[test]
+fn format_keeps_entries_with_whitespace_only_lines() {
+    let (before, diagnostics) = parse::parse(WHITESPACE_LINE).unwrap().into_parts();
+    assert!(diagnostics.is_empty());
+    let formatted = format::format(WHITESPACE_LINE, &Dialect::default()).unwrap();
+    assert!(formatted.contains("first paragraph\nsecond paragraph\n"));
+    let (after, diagnostics) = parse::parse(&formatted).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    assert_same_entries(&before, &after);
+}
+
+#[test]
+fn format_is_stable() {
+    let formatted = format::format(WHITESPACE_LINE, &Dialect::default()).unwrap();
+    assert_eq!(
+        format::format(&formatted, &Dialect::default()).unwrap(),
+        formatted
+    );
+}
+
+#[test]
+fn format_refuses_to_change_entries() {
+    // The first header can't be read, so the entry is left out until the
+    // spacing of its time is formatted
+    let logbook = WHITESPACE_LINE.replace("8:00 PM finished 8:10", "8:00   PM finished 8:10");
+    let error = format::format(&logbook, &Dialect::default()).unwrap_err();
+    assert_eq!(error.code(), Code::FormatChanged);
+}
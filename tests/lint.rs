@@ -0,0 +1,97 @@
+use logbook_integrity::lint::{Lints, Rule};
+use logbook_integrity::parse;
+use time::macros::datetime;
+
+/// A logbook whose second entry was written after midnight, as the README's
+/// example is, and whose third entry was written on a page in a zone east of
+/// UTC
+const LOGBOOK: &str = "\
+Entries from 1/1/2000 - 1 to 1/3/2000 - 3
+
+-----
+
+1-2
+1/1/2000-1/3/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/3/2000 started 1:00 AM finished 1:10 AM
+    B
+text
+
+-----
+
+3-3
+1/3/2000-1/3/2000
+UTC+10
+
+Entry 3: 1/3/2000 started 8:00 PM finished 8:10 PM
+    C
+text
+";
+
+/// A logbook whose second entry was written four days after the first
+const GAP: &str = "\
+Entries from 1/1/2000 - 1 to 1/5/2000 - 2
+
+-----
+
+1-2
+1/1/2000-1/5/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/5/2000 started 8:00 PM finished 8:10 PM
+    B
+text
+";
+
+/// Check a logbook with one rule, giving the entry numbers of its warnings
+fn check(logbook: &str, rule: Rule, lints: &mut Lints) -> Vec<u32> {
+    let logbook = parse::parse(logbook).unwrap().into_parts().0;
+    lints.enable(rule);
+    lints
+        .check(&logbook)
+        .iter()
+        .inspect(|diagnostic| assert_eq!(diagnostic.code(), rule.code()))
+        .map(|diagnostic| diagnostic.mark().unwrap().entry_number())
+        .collect()
+}
+
+#[test]
+fn date_drift_allows_entries_written_after_midnight() {
+    assert!(parse::parse(LOGBOOK).unwrap().diagnostics().is_empty());
+    assert!(check(LOGBOOK, Rule::DateDrift, &mut Lints::new()).is_empty());
+}
+
+#[test]
+fn date_drift_reports_entries_recorded_days_after_the_previous_one() {
+    let mut lints = Lints::new();
+    assert_eq!(check(GAP, Rule::DateDrift, &mut lints), [2]);
+    lints.set_max_drift(time::Duration::days(3));
+    assert!(check(GAP, Rule::DateDrift, &mut lints).is_empty());
+}
+
+#[test]
+fn future_entry_uses_the_zone_of_each_entry() {
+    let mut lints = Lints::new();
+    // 8:00 PM UTC+10 on 1/3/2000 has already passed
+    lints.set_now(datetime!(2000-01-03 11:00 UTC));
+    assert!(check(LOGBOOK, Rule::FutureEntry, &mut lints).is_empty());
+    lints.set_now(datetime!(2000-01-03 9:00 UTC));
+    assert_eq!(check(LOGBOOK, Rule::FutureEntry, &mut lints), [3]);
+}
+
+#[test]
+fn future_entry_allows_entries_without_a_zone_to_be_in_any_zone() {
+    let mut lints = Lints::new();
+    // 1:00 AM on 1/3/2000 has passed in zones far enough east of UTC
+    lints.set_now(datetime!(2000-01-02 12:00 UTC));
+    assert_eq!(check(LOGBOOK, Rule::FutureEntry, &mut lints), [3]);
+    lints.set_now(datetime!(2000-01-02 10:00 UTC));
+    assert_eq!(check(LOGBOOK, Rule::FutureEntry, &mut lints), [2, 3]);
+}
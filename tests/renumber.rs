@@ -0,0 +1,131 @@
+use logbook_integrity::dialect::Dialect;
+use logbook_integrity::{parse, renumber, Mark};
+use time::macros::date;
+
+/// A logbook over two pages, as it was before an entry for 1/2/2000 was
+/// inserted
+const BEFORE_INSERT: &str = "\
+Entries from 1/1/2000 - 1 to 1/4/2000 - 3
+
+-----
+
+1-2
+1/1/2000-1/3/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/3/2000 started 8:00 PM finished 8:10 PM
+    C
+text
+
+-----
+
+3-3
+1/4/2000-1/4/2000
+
+Entry 3: 1/4/2000 started 8:00 PM finished 8:10 PM
+    D
+text
+";
+
+/// Renumber a logbook from a date and number, checking that the result has no
+/// problems
+fn renumbered(logbook: &str, from: Mark) -> String {
+    let renumbered = renumber::renumber(logbook, &from, &Dialect::default()).unwrap();
+    let (_, diagnostics) = parse::parse(&renumbered).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    renumbered
+}
+
+#[test]
+fn renumber_after_inserting_an_entry() {
+    let inserted = BEFORE_INSERT.replace(
+        "Entry 2: 1/3/2000",
+        "Entry 2: 1/2/2000 started 8:00 PM finished 8:10 PM\n    B\ntext\n\nEntry 2: 1/3/2000",
+    );
+    let expected = "\
+Entries from 1/1/2000 - 1 to 1/4/2000 - 4
+
+-----
+
+1-3
+1/1/2000-1/3/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/2/2000 started 8:00 PM finished 8:10 PM
+    B
+text
+
+Entry 3: 1/3/2000 started 8:00 PM finished 8:10 PM
+    C
+text
+
+-----
+
+4-4
+1/4/2000-1/4/2000
+
+Entry 4: 1/4/2000 started 8:00 PM finished 8:10 PM
+    D
+text
+";
+    assert_eq!(
+        renumbered(&inserted, Mark::new(date!(2000 - 01 - 03), 3)),
+        expected
+    );
+}
+
+#[test]
+fn renumber_after_removing_an_entry() {
+    let removed = BEFORE_INSERT.replace(
+        "\nEntry 2: 1/3/2000 started 8:00 PM finished 8:10 PM\n    C\ntext\n",
+        "",
+    );
+    // The page the entry was removed from has its header updated by hand, but
+    // later pages don't
+    let removed = removed.replace("1-2\n1/1/2000-1/3/2000", "1-1\n1/1/2000-1/1/2000");
+    let expected = "\
+Entries from 1/1/2000 - 1 to 1/4/2000 - 2
+
+-----
+
+1-1
+1/1/2000-1/1/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+-----
+
+2-2
+1/4/2000-1/4/2000
+
+Entry 2: 1/4/2000 started 8:00 PM finished 8:10 PM
+    D
+text
+";
+    let renumbered = renumber::renumber(
+        &removed,
+        &Mark::new(date!(2000 - 01 - 04), 2),
+        &Dialect::default(),
+    )
+    .unwrap();
+    assert_eq!(renumbered, expected);
+}
+
+#[test]
+fn renumber_from_a_date_with_no_entry_changes_nothing() {
+    let renumbered = renumber::renumber(
+        BEFORE_INSERT,
+        &Mark::new(date!(2000 - 02 - 01), 10),
+        &Dialect::default(),
+    )
+    .unwrap();
+    assert_eq!(renumbered, BEFORE_INSERT);
+}
@@ -0,0 +1,129 @@
+use logbook_integrity::search::{Query, Search};
+use logbook_integrity::subject::SubjectPath;
+use logbook_integrity::{Block, Entry, Logbook, Mark};
+use time::macros::date;
+use time::{Date, Duration};
+
+/// Create a logbook with an entry on each of the first days of 2000, each
+/// with a block of a subject and text
+fn logbook(entries: &[(&str, &str)]) -> Logbook {
+    let entries = (1..)
+        .zip(entries)
+        .map(|(number, (subject, text))| {
+            let date = date!(2000 - 01 - 01) + Duration::days(i64::from(number) - 1);
+            let started = date.with_hms(20, 0, 0).unwrap();
+            Entry::new(
+                Mark::new(date, number),
+                started,
+                started + Duration::minutes(10),
+                vec![Block::new((*subject).to_owned(), (*text).to_owned())],
+            )
+        })
+        .collect();
+    Logbook::new(Mark::new(date!(2000 - 01 - 01), 1), None, entries)
+}
+
+/// Find the text matched by a query in some text
+fn found<'a>(query: &Query, text: &'a str) -> Option<Vec<&'a str>> {
+    let matches = query.find(text)?;
+    Some(matches.into_iter().map(|found| &text[found]).collect())
+}
+
+/// Search some logbooks, giving the effective dates of the entries with hits
+fn dates(search: &Search, logbooks: &[Logbook]) -> Vec<Date> {
+    search
+        .find(logbooks)
+        .iter()
+        .map(|hit| hit.entry().position().effective_date())
+        .collect()
+}
+
+#[test]
+fn every_word_must_match() {
+    let query = Query::words("rain walk", false).unwrap();
+    assert_eq!(
+        found(&query, "A walk in the rain, then a long walk home"),
+        Some(vec!["walk", "rain", "walk"])
+    );
+    assert_eq!(found(&query, "A walk in the sun"), None);
+    assert_eq!(Query::words("  ", false).unwrap_err(), "query has no words");
+}
+
+#[test]
+fn quoted_phrases_match_together_in_order() {
+    let query = Query::words("\"long walk\" home", false).unwrap();
+    assert_eq!(
+        found(&query, "a long\n  walk home"),
+        Some(vec!["long\n  walk", "home"])
+    );
+    assert_eq!(found(&query, "a walk, long, home"), None);
+    // Words are matched literally, even within phrases
+    let query = Query::words("\"a.b (c)\"", false).unwrap();
+    assert_eq!(found(&query, "a.b (c)"), Some(vec!["a.b (c)"]));
+    assert_eq!(found(&query, "axb c"), None);
+}
+
+#[test]
+fn case_is_only_ignored_when_asked() {
+    let sensitive = Query::words("Rain", false).unwrap();
+    assert_eq!(found(&sensitive, "rain"), None);
+    let insensitive = Query::words("Rain", true).unwrap();
+    assert_eq!(
+        found(&insensitive, "RAIN and rain"),
+        Some(vec!["RAIN", "rain"])
+    );
+    let regex = Query::regex("r[a-z]+n", true).unwrap();
+    assert_eq!(found(&regex, "RAIN"), Some(vec!["RAIN"]));
+}
+
+#[test]
+fn regular_expressions_are_checked() {
+    let query = Query::regex(r"\bw\w+k\b", false).unwrap();
+    assert_eq!(found(&query, "a walk, a wok"), Some(vec!["walk", "wok"]));
+    // Empty matches don't count
+    assert_eq!(found(&Query::regex("x*", false).unwrap(), "abc"), None);
+    let error = Query::regex("(unclosed", false).unwrap_err();
+    assert!(error.contains("unclosed group"), "{error}");
+}
+
+#[test]
+fn searches_are_limited_by_subject_and_date() {
+    let logbooks = [logbook(&[
+        ("Work", "rain"),
+        ("Work/Meetings", "rain"),
+        ("Workshop", "rain"),
+        ("Home", "rain"),
+    ])];
+    let mut search = Search::new(Query::words("rain", false).unwrap());
+    assert_eq!(dates(&search, &logbooks).len(), 4);
+    search.set_subject(Some(SubjectPath::parse("Work")));
+    assert_eq!(
+        dates(&search, &logbooks),
+        [date!(2000 - 01 - 01), date!(2000 - 01 - 02)]
+    );
+    search.set_subject(None);
+    search.set_from(Some(date!(2000 - 01 - 02)));
+    search.set_to(Some(date!(2000 - 01 - 03)));
+    assert_eq!(
+        dates(&search, &logbooks),
+        [date!(2000 - 01 - 02), date!(2000 - 01 - 03)]
+    );
+    search.set_subject(Some(SubjectPath::parse("Home")));
+    assert!(dates(&search, &logbooks).is_empty());
+}
+
+#[test]
+fn hits_are_rendered_with_context() {
+    let text = format!("{} rain {}", "before ".repeat(10), "after ".repeat(10));
+    let logbooks = [logbook(&[("Work", &text)])];
+    let search = Search::new(Query::words("rain", false).unwrap());
+    let hits = search.find(&logbooks);
+    let rendered = hits[0].render(false);
+    let mut lines = rendered.lines();
+    assert_eq!(lines.next(), Some("2000-01-01 #1 Work"));
+    let line = lines.next().unwrap();
+    assert!(line.starts_with("    ...before"), "{line}");
+    assert!(line.contains(" rain "), "{line}");
+    assert!(line.ends_with("after..."), "{line}");
+    assert!(hits[0].render(true).contains("\x1b[1;31mrain\x1b[0m"));
+}
@@ -0,0 +1,153 @@
+use logbook_integrity::stats::{self, DateRange, Grouping, Summary};
+use logbook_integrity::{Block, Entry, Logbook, Mark};
+use time::macros::{date, datetime, time};
+use time::{Date, Duration, PrimitiveDateTime};
+
+/// Create an entry for an effective date, started at a time and taking some
+/// minutes, with a block for each subject
+fn entry(date: Date, started: PrimitiveDateTime, minutes: i64, subjects: &[&str]) -> Entry {
+    Entry::new(
+        Mark::new(date, 1),
+        started,
+        started + Duration::minutes(minutes),
+        subjects
+            .iter()
+            .map(|subject| Block::new((*subject).to_owned(), "some words".to_owned()))
+            .collect(),
+    )
+}
+
+/// Create a logbook of entries started at 8 PM on each effective date
+fn logbook(dates: &[Date]) -> Logbook {
+    let entries = dates
+        .iter()
+        .map(|date| entry(*date, date.with_hms(20, 0, 0).unwrap(), 10, &["A"]))
+        .collect::<Vec<_>>();
+    let start = entries[0].position().clone();
+    Logbook::new(start, None, entries)
+}
+
+#[test]
+fn summaries_use_nearest_rank_percentiles() {
+    let summary = Summary::of((1..=10).map(f64::from).collect()).unwrap();
+    assert_eq!(
+        (summary.mean(), summary.median(), summary.percentile()),
+        (5.5, 5.5, 9.0)
+    );
+    let summary = Summary::of(vec![3.0, 1.0, 2.0]).unwrap();
+    assert_eq!(
+        (summary.mean(), summary.median(), summary.percentile()),
+        (2.0, 2.0, 3.0)
+    );
+    let summary = Summary::of(vec![4.0]).unwrap();
+    assert_eq!(summary.percentile(), 4.0);
+    assert_eq!(Summary::of(Vec::new()), None);
+}
+
+#[test]
+fn streaks_and_gaps_are_found_between_effective_dates() {
+    let logbook = logbook(&[
+        date!(2000 - 01 - 01),
+        date!(2000 - 01 - 02),
+        date!(2000 - 01 - 03),
+        date!(2000 - 01 - 05),
+        date!(2000 - 01 - 09),
+        date!(2000 - 01 - 10),
+    ]);
+    let stats = &stats::stats(&[logbook], Grouping::All)[0];
+    assert_eq!(
+        stats.longest_streak(),
+        Some(DateRange::new(date!(2000 - 01 - 01), date!(2000 - 01 - 03)))
+    );
+    assert_eq!(
+        stats.longest_gaps(),
+        [
+            DateRange::new(date!(2000 - 01 - 06), date!(2000 - 01 - 08)),
+            DateRange::new(date!(2000 - 01 - 04), date!(2000 - 01 - 04)),
+        ]
+    );
+    // Equally long streaks and gaps are reported earliest first
+    let logbook = self::logbook(&[
+        date!(2000 - 01 - 01),
+        date!(2000 - 01 - 03),
+        date!(2000 - 01 - 05),
+    ]);
+    let stats = &stats::stats(&[logbook], Grouping::All)[0];
+    assert_eq!(
+        stats.longest_streak(),
+        Some(DateRange::new(date!(2000 - 01 - 01), date!(2000 - 01 - 01)))
+    );
+    assert_eq!(stats.longest_gaps()[0].first(), date!(2000 - 01 - 02));
+}
+
+#[test]
+fn average_start_wraps_around_midnight() {
+    let before = entry(date!(2000 - 01 - 01), datetime!(2000-01-01 23:00), 10, &[]);
+    let after = entry(date!(2000 - 01 - 02), datetime!(2000-01-03 1:00), 10, &[]);
+    let stats = stats::Stats::new(None, &[&before, &after]);
+    assert_eq!(stats.average_start(), Some(time!(0:00)));
+    let earlier = entry(date!(2000 - 01 - 03), datetime!(2000-01-03 22:00), 10, &[]);
+    let stats = stats::Stats::new(None, &[&before, &earlier]);
+    assert_eq!(stats.average_start(), Some(time!(22:30)));
+}
+
+#[test]
+fn stats_cover_durations_and_subjects() {
+    let first = entry(
+        date!(2000 - 01 - 01),
+        datetime!(2000-01-01 20:00),
+        10,
+        &["B", "A"],
+    );
+    let second = entry(
+        date!(2000 - 01 - 02),
+        datetime!(2000-01-02 20:00),
+        30,
+        &["A"],
+    );
+    let stats = stats::Stats::new(None, &[&first, &second]);
+    assert_eq!(stats.entries(), 2);
+    assert_eq!(stats.total_duration(), Duration::minutes(40));
+    assert_eq!(stats.duration().unwrap().mean(), 20.0);
+    assert_eq!(stats.blocks_per_entry().unwrap().median(), 1.5);
+    assert_eq!(stats.words_per_entry().unwrap().percentile(), 4.0);
+    assert_eq!(
+        stats.top_subjects(),
+        [("A".to_owned(), 2), ("B".to_owned(), 1)]
+    );
+}
+
+#[test]
+fn stats_are_grouped_by_month_or_year() {
+    let logbooks = [
+        logbook(&[
+            date!(2000 - 01 - 30),
+            date!(2000 - 01 - 31),
+            date!(2000 - 02 - 01),
+        ]),
+        logbook(&[date!(2001 - 01 - 01)]),
+    ];
+    let groups = |grouping| {
+        stats::stats(&logbooks, grouping)
+            .iter()
+            .map(|stats| (stats.label().map(str::to_owned), stats.entries()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(groups(Grouping::All), [(None, 4)]);
+    assert_eq!(
+        groups(Grouping::Month),
+        [
+            (Some("2000-01".to_owned()), 2),
+            (Some("2000-02".to_owned()), 1),
+            (Some("2001-01".to_owned()), 1),
+        ]
+    );
+    assert_eq!(
+        groups(Grouping::Year),
+        [(Some("2000".to_owned()), 3), (Some("2001".to_owned()), 1)]
+    );
+    // Without entries there is one empty group of every entry, and no month
+    // or year groups
+    assert_eq!(stats::stats(&[], Grouping::All)[0].entries(), 0);
+    assert!(stats::stats(&[], Grouping::Month).is_empty());
+}
@@ -0,0 +1,76 @@
+use logbook_integrity::diagnostic::Code;
+use logbook_integrity::dialect::Dialect;
+use logbook_integrity::syntax::SyntaxTree;
+use logbook_integrity::{format, parse, write};
+
+/// A logbook written with ISO 8601 dates and 24-hour times
+const ISO: &str = "\
+Entries from 2000-01-01 - 1 to 2000-01-02 - 2
+
+-----
+
+1-2
+2000-01-01-2000-01-02
+
+Entry 1: 2000-01-01 started 20:00 finished 20:10
+    A
+text
+
+Entry 2: 2000-01-02 started 23:30 finished 00:15
+    B
+text
+";
+
+/// A logbook written in the default style, but with one ISO 8601 date and one
+/// 24-hour time
+const MIXED: &str = "\
+Entries from 1/1/2000 - 1 to 1/2/2000 - 2
+
+-----
+
+1-2
+1/1/2000-2000-01-02
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/2/2000 started 20:00 finished 8:10 PM
+    B
+text
+";
+
+#[test]
+fn iso_dates_and_24_hour_times_round_trip() {
+    let (logbook, diagnostics) = parse::parse(ISO).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    assert_eq!(logbook.len(), 2);
+    assert_eq!(write::write(&logbook), ISO);
+    assert_eq!(format::format(ISO, &Dialect::default()).unwrap(), ISO);
+}
+
+#[test]
+fn page_header_ranges_split_where_both_dates_read() {
+    let tree = SyntaxTree::new(MIXED);
+    let dates = tree.pages()[0].header().unwrap().dates().unwrap();
+    assert_eq!(tree.text(dates.start()), "1/1/2000");
+    assert_eq!(tree.text(dates.end().unwrap()), "2000-01-02");
+    let tree = SyntaxTree::new(ISO);
+    let dates = tree.pages()[0].header().unwrap().dates().unwrap();
+    assert_eq!(tree.text(dates.start()), "2000-01-01");
+    assert_eq!(tree.text(dates.end().unwrap()), "2000-01-02");
+}
+
+#[test]
+fn mixed_styles_are_warned_about() {
+    let (_, diagnostics) = parse::parse(MIXED).unwrap().into_parts();
+    let lines = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            assert_eq!(diagnostic.code(), Code::MixedStyles, "{diagnostic:?}");
+            assert_eq!(diagnostic.labels().len(), 1);
+            diagnostic.location().unwrap().line()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lines, [6, 12]);
+}
@@ -0,0 +1,144 @@
+use logbook_integrity::subject::{self, Aliases, SubjectPath, SubjectTree};
+use logbook_integrity::{Block, Entry, Logbook, Mark};
+use time::macros::date;
+use time::Duration;
+
+/// Create a logbook with an entry on each of the first days of 2000, each
+/// with blocks of some subjects
+fn logbook(entries: &[&[&str]]) -> Logbook {
+    let entries = (1..)
+        .zip(entries)
+        .map(|(number, subjects)| {
+            let date = date!(2000 - 01 - 01) + Duration::days(i64::from(number) - 1);
+            let started = date.with_hms(20, 0, 0).unwrap();
+            Entry::new(
+                Mark::new(date, number),
+                started,
+                started + Duration::minutes(10),
+                subjects
+                    .iter()
+                    .map(|subject| Block::new((*subject).to_owned(), "text".to_owned()))
+                    .collect(),
+            )
+        })
+        .collect();
+    Logbook::new(Mark::new(date!(2000 - 01 - 01), 1), None, entries)
+}
+
+#[test]
+fn subjects_are_parsed_into_levels() {
+    let path = SubjectPath::parse(" Entries / Format//");
+    assert_eq!(path.segments(), ["Entries", "Format"]);
+    assert_eq!(path.name(), Some("Format"));
+    assert_eq!(path.parent(), Some(SubjectPath::parse("Entries")));
+    assert_eq!(path.to_string(), "Entries/Format");
+    assert!(SubjectPath::parse("Entries").contains(&path));
+    assert!(!path.contains(&SubjectPath::parse("Entries")));
+    assert!(!SubjectPath::parse("Entr").contains(&path));
+    assert!(SubjectPath::parse("").contains(&path));
+    // A block's subject is parsed along with it, and again when it changes
+    let mut block = Block::new("Entries/Format".to_owned(), String::new());
+    assert_eq!(block.subject_path(), &path);
+    block.set_subject("Entries".to_owned());
+    assert_eq!(block.subject_path(), &SubjectPath::parse("Entries"));
+}
+
+#[test]
+fn subject_trees_count_blocks_and_their_first_and_last_entries() {
+    let tree = SubjectTree::new(&[logbook(&[
+        &["Work", "Work/Meetings"],
+        &["Home"],
+        &["Work/Meetings", "Work/Meetings"],
+    ])]);
+    let work = tree.find(&SubjectPath::parse("Work")).unwrap();
+    assert_eq!((work.count(), work.total()), (1, 4));
+    let meetings = tree.find(&SubjectPath::parse("Work/Meetings")).unwrap();
+    assert_eq!((meetings.count(), meetings.total()), (3, 3));
+    assert_eq!(meetings.first(), Some(&Mark::new(date!(2000 - 01 - 01), 1)));
+    assert_eq!(meetings.last(), Some(&Mark::new(date!(2000 - 01 - 03), 3)));
+    let home = tree.find(&SubjectPath::parse("Home")).unwrap();
+    assert_eq!(home.first(), home.last());
+    assert_eq!(tree.root().total(), 5);
+    assert!(tree.find(&SubjectPath::parse("Work/Calls")).is_none());
+    assert_eq!(
+        tree.to_string(),
+        "\
+Home (1, 2000-01-02 #2)
+Work (4, 1 directly, 2000-01-01 #1 to 2000-01-03 #3)
+└── Meetings (3, 2000-01-01 #1 to 2000-01-03 #3)
+"
+    );
+}
+
+#[test]
+fn matching_blocks_are_within_a_subject() {
+    let logbooks = [logbook(&[
+        &["Work", "Workshop"],
+        &["Work/Meetings", "Home"],
+    ])];
+    let work = SubjectPath::parse("Work");
+    let matching = subject::matching(&logbooks, &work)
+        .map(|(entry, block)| (entry.position().entry_number(), block.subject().as_str()))
+        .collect::<Vec<_>>();
+    assert_eq!(matching, [(1, "Work"), (2, "Work/Meetings")]);
+    let everything = SubjectPath::parse("");
+    assert_eq!(subject::matching(&logbooks, &everything).count(), 4);
+}
+
+#[test]
+fn aliases_are_followed_to_canonical_subjects() {
+    let aliases = Aliases::parse(
+        "\
+# Old names
+Work = Job
+Job = \"Career\"
+Work/Calls = Phone
+",
+    )
+    .unwrap();
+    let canonical = |subject| aliases.canonical(&SubjectPath::parse(subject)).to_string();
+    // Chains are followed, moving subjects within old subjects along
+    assert_eq!(canonical("Work"), "Career");
+    assert_eq!(canonical("Work/Meetings"), "Career/Meetings");
+    // The longest old subject is used
+    assert_eq!(canonical("Work/Calls/Late"), "Phone/Late");
+    assert_eq!(canonical("Home"), "Home");
+    let mut logbooks = [logbook(&[&["Work/Meetings", "Home"]])];
+    aliases.apply(&mut logbooks);
+    let subjects = logbooks[0].entries()[0]
+        .contents()
+        .iter()
+        .map(|block| block.subject().as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(subjects, ["Career/Meetings", "Home"]);
+}
+
+#[test]
+fn aliases_are_rejected_when_they_go_round_in_circles() {
+    let mut aliases = Aliases::new();
+    aliases.add("A".into(), "B".into()).unwrap();
+    aliases.add("B".into(), "C".into()).unwrap();
+    assert_eq!(
+        aliases.add("C".into(), "A".into()),
+        Err("aliases of C lead back to it".to_owned())
+    );
+    // Aliasing a subject to one within it would grow it forever
+    assert_eq!(
+        aliases.add("D".into(), "D/E".into()),
+        Err("aliases of D lead back to it".to_owned())
+    );
+    // Rejected aliases aren't kept
+    assert_eq!(aliases.iter().count(), 2);
+    assert_eq!(aliases.canonical(&"C".into()).to_string(), "C");
+    assert_eq!(
+        aliases.add("A".into(), "C".into()),
+        Err("A is already aliased to B".to_owned())
+    );
+    assert_eq!(
+        aliases.add("".into(), "C".into()),
+        Err("aliased subjects must not be empty".to_owned())
+    );
+    let error = Aliases::parse("A = B\nB = A\n").unwrap_err();
+    assert!(error.message().contains("lead back"), "{error:?}");
+    assert!(Aliases::parse("A B\n").is_err());
+}
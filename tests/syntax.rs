@@ -0,0 +1,156 @@
+use logbook_integrity::diagnostic::Span;
+use logbook_integrity::parse;
+use logbook_integrity::syntax::{Edit, LineNode, SyntaxTree};
+
+/// A logbook with an entry continued onto a second page
+const LOGBOOK: &str = "\
+Entries from 1/1/2000 - 1 to 1/2/2000 - 2
+
+-----
+
+1-2
+1/1/2000-1/2/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+first line
+second line
+
+Entry 2: 1/2/2000 started 8:00 PM finished 8:10 PM
+    B
+text (->)
+
+-----
+
+2-2
+1/2/2000-1/2/2000
+
+(->) more text
+";
+
+/// Check that the spans of a tree cover its source exactly once, in order,
+/// and that applying no edits gives back the source
+fn assert_lossless(source: &str) {
+    let tree = SyntaxTree::new(source);
+    assert_eq!(tree.source(), source);
+    assert_eq!(tree.edit(&[]), source);
+    let mut spans = vec![tree.preamble().span()];
+    for page in tree.pages() {
+        let mut parts = vec![page.marker()];
+        parts.extend(page.header().map(|header| header.span()));
+        parts.extend(page.entries().iter().map(|entry| entry.span()));
+        parts.extend(page.trivia());
+        parts.sort_by_key(|span| span.start());
+        assert_eq!(parts.first().unwrap().start(), page.span().start());
+        assert_eq!(parts.last().unwrap().end(), page.span().end());
+        spans.extend(parts);
+    }
+    let mut position = 0;
+    let mut rebuilt = String::new();
+    for span in spans {
+        assert_eq!(span.start(), position, "gap or overlap in {source:?}");
+        rebuilt.push_str(tree.text(span));
+        position = span.end();
+    }
+    assert_eq!(position, source.len());
+    assert_eq!(rebuilt, source);
+}
+
+#[test]
+fn tree_is_lossless() {
+    assert_lossless(LOGBOOK);
+    assert_lossless("");
+    assert_lossless("\n\n\n");
+    assert_lossless(LOGBOOK.trim_end());
+    assert_lossless(&format!("\n\n{LOGBOOK}\n\n\n"));
+}
+
+#[test]
+fn tree_is_lossless_with_trailing_whitespace() {
+    let spaced = LOGBOOK.replace('\n', " \t\n");
+    assert_lossless(&spaced);
+    // Lines of only whitespace don't separate components
+    let blank = LOGBOOK.replace("first line\n", "first line\n \n");
+    assert_lossless(&blank);
+    let tree = SyntaxTree::new(&blank);
+    assert_eq!(tree.pages()[0].entries().len(), 2);
+}
+
+#[test]
+fn tree_is_lossless_with_crlf() {
+    assert_lossless(&LOGBOOK.replace('\n', "\r\n"));
+    assert_lossless(&LOGBOOK.replace("\n\n", "\n\r\n"));
+}
+
+#[test]
+fn edits_keep_text_outside_their_spans() {
+    let tree = SyntaxTree::new(LOGBOOK);
+    let entries = tree
+        .pages()
+        .iter()
+        .flat_map(|page| page.entries())
+        .collect::<Vec<_>>();
+    let header = entries[0].header().unwrap().span();
+    let LineNode::Text {
+        continues: Some(marker),
+        ..
+    } = *entries[1].lines().last().unwrap()
+    else {
+        panic!("expected a continued line");
+    };
+    let end = Span::new(LOGBOOK.len(), LOGBOOK.len());
+    // Given out of order, with one insertion
+    let edited = tree.edit(&[
+        Edit::new(end, "appended\n".to_owned()),
+        Edit::new(marker, "[->]".to_owned()),
+        Edit::new(header, "header".to_owned()),
+    ]);
+    let expected = [
+        &LOGBOOK[..header.start()],
+        "header",
+        &LOGBOOK[header.end()..marker.start()],
+        "[->]",
+        &LOGBOOK[marker.end()..],
+        "appended\n",
+    ]
+    .concat();
+    assert_eq!(edited, expected);
+}
+
+#[test]
+fn adjacent_edits_keep_text_outside_their_spans() {
+    let tree = SyntaxTree::new(LOGBOOK);
+    let marker = tree.pages()[0].marker();
+    let trivia = tree.pages()[0].trivia()[0];
+    assert_eq!(marker.end(), trivia.start());
+    let edited = tree.edit(&[
+        Edit::new(marker, "=====".to_owned()),
+        Edit::new(trivia, "\n\n\n".to_owned()),
+    ]);
+    let expected = [
+        &LOGBOOK[..marker.start()],
+        "=====\n\n\n",
+        &LOGBOOK[trivia.end()..],
+    ]
+    .concat();
+    assert_eq!(edited, expected);
+}
+
+#[test]
+fn parser_reads_entries_where_the_tree_splits_them() {
+    let blank = LOGBOOK.replace("first line\n", "first line\n \n");
+    let tree = SyntaxTree::new(&blank);
+    let (logbook, diagnostics) = parse::parse_tree(&tree).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    let texts = logbook
+        .iter()
+        .map(|entry| {
+            entry.contents()[0]
+                .text()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["first line second line", "text more text"]);
+}
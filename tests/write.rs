@@ -0,0 +1,79 @@
+use logbook_integrity::{parse, write, Logbook};
+
+/// A logbook with an entry continued over two page breaks
+const MULTI_PAGE: &str = "\
+Entries from 1/1/2000 - 1 to 1/3/2000 - 3
+
+-----
+
+1-2
+1/1/2000-1/2/2000
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    Entries/Format
+Each entry starts with a header.
+    Entries/Blocks
+An entry can have multiple blocks.
+
+Entry 2: 1/2/2000 started 9:00 PM finished 9:40 PM
+    Entries/Pages
+This entry is long enough (->)
+
+-----
+
+2-2
+1/2/2000-1/2/2000
+
+(->) to run onto a third page,
+with several lines on each (->)
+
+-----
+
+2-3
+1/2/2000-1/3/2000
+
+(->) page it is written on.
+    Entries/More
+And a second block.
+
+Entry 3: 1/3/2000 started 8:00 PM finished 8:10 PM
+    Entries/Format
+The last entry.
+";
+
+#[test]
+fn write_round_trips_multi_page_logbook() {
+    let (parsed, diagnostics) = parse::parse(MULTI_PAGE).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    assert_eq!(parsed.pages().len(), 3);
+    let written = write::write(&parsed);
+    let (reparsed, diagnostics) = parse::parse(&written).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{written}\n{diagnostics:?}");
+    assert_eq!(reparsed, parsed);
+    assert_eq!(reparsed.pages(), parsed.pages());
+}
+
+#[test]
+fn write_is_stable() {
+    let (parsed, _) = parse::parse(MULTI_PAGE).unwrap().into_parts();
+    let written = write::write(&parsed);
+    let (reparsed, _) = parse::parse(&written).unwrap().into_parts();
+    assert_eq!(write::write(&reparsed), written);
+}
+
+#[test]
+fn write_round_trips_logbook_built_in_code() {
+    let (parsed, _) = parse::parse(MULTI_PAGE).unwrap().into_parts();
+    let mut built = Logbook::new(
+        parsed.start().clone(),
+        parsed.end().cloned(),
+        parsed.entries().to_vec(),
+    );
+    built.set_style(parsed.style());
+    // Pages are left out when comparing logbooks
+    assert!(built.pages().is_empty());
+    assert_eq!(built, parsed);
+    let (reparsed, diagnostics) = parse::parse(&write::write(&built)).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    assert_eq!(reparsed, built);
+}
@@ -0,0 +1,87 @@
+use logbook_integrity::parse;
+use logbook_integrity::zone::Zone;
+use time::macros::{datetime, offset};
+
+#[test]
+fn fixed_zones_are_within_offsets_in_use() {
+    let offset = |text: &str| match Zone::parse(text) {
+        Some(Zone::Fixed(offset)) => Some(offset),
+        _ => None,
+    };
+    assert_eq!(offset("UTC"), Some(offset!(UTC)));
+    assert_eq!(offset("UTC+2"), Some(offset!(+2)));
+    assert_eq!(offset("UTC-5:30"), Some(offset!(-5:30)));
+    assert_eq!(offset("UTC+13:45"), Some(offset!(+13:45)));
+    assert_eq!(offset("UTC+14"), Some(offset!(+14)));
+    assert_eq!(offset("UTC-12"), Some(offset!(-12)));
+    for text in [
+        "UTC+14:30",
+        "UTC+15",
+        "UTC-12:30",
+        "UTC-13",
+        "UTC+5:60",
+        "UTC+",
+        "UTC+-2",
+        "UTC 2",
+        "UTC+two",
+    ] {
+        assert_eq!(offset(text), None, "{text}");
+    }
+}
+
+#[test]
+fn named_zones_resolve_daylight_saving_time_changes() {
+    let berlin = Zone::parse("Europe/Berlin").unwrap();
+    assert_eq!(berlin.offset_at(datetime!(2021-01-15 12:00)), offset!(+1));
+    assert_eq!(berlin.offset_at(datetime!(2021-07-15 12:00)), offset!(+2));
+    // Clocks went forward from 2:00 to 3:00, so 2:30 never happened and
+    // takes the offset after the change
+    assert_eq!(berlin.offset_at(datetime!(2021-03-28 2:30)), offset!(+2));
+    // Clocks went back from 3:00 to 2:00, so 2:30 happened twice and takes
+    // the earlier offset
+    assert_eq!(berlin.offset_at(datetime!(2021-10-31 2:30)), offset!(+2));
+    assert_eq!(berlin.offset_at(datetime!(2021-10-31 3:30)), offset!(+1));
+}
+
+#[test]
+fn entries_inherit_the_zone_of_their_page() {
+    let logbook = "\
+Entries from 1/1/2000 - 1 to 1/2/2000 - 2
+
+-----
+
+1-2
+1/1/2000-1/2/2000
+Europe/Berlin
+
+Entry 1: 1/1/2000 started 8:00 PM finished 8:10 PM
+    A
+text
+
+Entry 2: 1/2/2000 started 8:00 PM UTC-5 finished 8:10 PM
+    B
+text
+";
+    let (logbook, diagnostics) = parse::parse(logbook).unwrap().into_parts();
+    assert!(diagnostics.is_empty(), "{diagnostics:?}");
+    let zones = logbook
+        .iter()
+        .map(|entry| {
+            (
+                entry.started_zone().unwrap().to_string(),
+                entry.finished_zone().unwrap().to_string(),
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        zones,
+        [
+            ("Europe/Berlin".to_owned(), "Europe/Berlin".to_owned()),
+            ("UTC-5".to_owned(), "UTC-5".to_owned()),
+        ]
+    );
+    assert_eq!(
+        logbook.entries()[0].started_at(),
+        datetime!(2000-01-01 20:00 +1)
+    );
+}